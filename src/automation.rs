@@ -0,0 +1,691 @@
+//! The key scheduling loops driven by `pks`.
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinSet;
+use tokio::time::sleep;
+
+use crate::config::{Action, Config, KeyAction};
+use crate::key_sender::KeySender;
+use crate::process_finder::TargetMode;
+use crate::timing::Timing;
+
+/// Cloneable handle used to stop a running automation
+#[derive(Clone)]
+pub struct Shutdown {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(false);
+        Self { tx: Arc::new(tx) }
+    }
+
+    /// Ask every loop holding this handle to stop
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Resolve once [`trigger`](Self::trigger) has been called
+    pub async fn wait(&self) {
+        let mut rx = self.tx.subscribe();
+        // The sender lives in self, so this can't fail
+        let _ = rx.wait_for(|&stopped| stopped).await;
+    }
+}
+
+/// Cloneable pause/resume switch shared by every sending loop
+#[derive(Clone)]
+pub struct Pause {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Pause {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pause {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(false);
+        Self { tx: Arc::new(tx) }
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.tx.send_replace(paused);
+    }
+
+    /// Flip the paused state, returning the new state
+    pub fn toggle(&self) -> bool {
+        let mut paused = false;
+        self.tx.send_modify(|state| {
+            *state = !*state;
+            paused = *state;
+        });
+        paused
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Resolve once the state is not paused
+    pub async fn wait_until_resumed(&self) {
+        let mut rx = self.tx.subscribe();
+        let _ = rx.wait_for(|&paused| !paused).await;
+    }
+}
+
+/// How `"target_mode": "all"` schedules the configured keys across processes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Broadcast {
+    /// Every process gets its own run of the loops, started when it is found
+    #[default]
+    Independent,
+    /// One run sends each step to every process before moving on
+    Lockstep,
+}
+
+/// What `pks` does when its target process exits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnTargetExit {
+    /// Hold the loops and search for the process again, resuming when it is back
+    #[default]
+    Rediscover,
+    /// Stop with `target_exit_code`
+    Exit,
+}
+
+/// Checks a window before keys are sent to it, answering with the window to
+/// use instead, or `None` to skip it for now
+pub type TargetCheck = Arc<dyn Fn(u64) -> Option<u64> + Send + Sync>;
+
+/// The windows a run sends to. Clones share the list, so processes can be
+/// added and dropped while the loops are running.
+#[derive(Clone)]
+pub struct Targets {
+    tx: Arc<watch::Sender<Vec<u64>>>,
+    check: Option<TargetCheck>,
+}
+
+impl fmt::Display for Broadcast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Broadcast::Independent => f.write_str("independent"),
+            Broadcast::Lockstep => f.write_str("lockstep"),
+        }
+    }
+}
+
+impl From<u64> for Targets {
+    fn from(window_id: u64) -> Self {
+        Self::new(vec![window_id])
+    }
+}
+
+impl Targets {
+    pub fn new(window_ids: Vec<u64>) -> Self {
+        let (tx, _) = watch::channel(Vec::new());
+        let targets = Self { tx: Arc::new(tx), check: None };
+        targets.set(window_ids);
+        targets
+    }
+
+    /// Run `check` on every window before each send
+    pub fn with_check(self, check: TargetCheck) -> Self {
+        Self { check: Some(check), ..self }
+    }
+
+    /// The current windows, lowest first
+    pub fn get(&self) -> Vec<u64> {
+        self.tx.borrow().clone()
+    }
+
+    /// Replace the windows, returning whether anything changed
+    pub fn set(&self, mut window_ids: Vec<u64>) -> bool {
+        window_ids.sort_unstable();
+        window_ids.dedup();
+        self.tx.send_if_modified(|current| {
+            let changed = *current != window_ids;
+            *current = window_ids;
+            changed
+        })
+    }
+
+    /// The windows to send to right now, after the check: replaced windows
+    /// are kept in the list, skipped ones are left out of the result only
+    fn live(&self) -> Vec<u64> {
+        let Some(check) = &self.check else {
+            return self.get();
+        };
+
+        let mut window_ids = self.get();
+        let mut live = Vec::new();
+        for window_id in &mut window_ids {
+            if let Some(checked) = check(*window_id) {
+                *window_id = checked;
+                live.push(checked);
+            }
+        }
+        self.set(window_ids);
+        live
+    }
+
+    fn subscribe(&self) -> watch::Receiver<Vec<u64>> {
+        self.tx.subscribe()
+    }
+}
+
+/// Counters describing a running automation
+#[derive(Debug, Default)]
+pub struct RunStats {
+    /// Completed passes through `key_sequence`
+    pub iterations: AtomicU64,
+    pub keys_sent: AtomicU64,
+    pub errors: AtomicU64,
+    /// PID of the process keys are sent to, 0 before one is found
+    pub target_pid: AtomicU64,
+    /// Every PID keys are sent to when targeting all matching processes
+    pub target_pids: Mutex<Vec<u64>>,
+}
+
+/// Point-in-time copy of [`RunStats`] plus the pause state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusReport {
+    pub paused: bool,
+    /// Waiting for the target process to come back
+    #[serde(default)]
+    pub target_lost: bool,
+    pub iterations: u64,
+    pub keys_sent: u64,
+    pub errors: u64,
+    pub target_pid: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_pids: Vec<u64>,
+}
+
+/// A pending reload, answered with `Err(reason)` if the new config was rejected
+pub type ReloadReply = oneshot::Sender<Result<(), String>>;
+
+/// Everything a running automation can be steered with from the outside
+#[derive(Clone)]
+pub struct RunControl {
+    pub shutdown: Shutdown,
+    pub pause: Pause,
+    /// Holds the loops while the target process is gone, apart from `pause`
+    /// so a user's resume doesn't send keys into nothing
+    pub target_lost: Pause,
+    pub stats: Arc<RunStats>,
+    reload_tx: mpsc::UnboundedSender<ReloadReply>,
+    reload_rx: Arc<Mutex<Option<mpsc::UnboundedReceiver<ReloadReply>>>>,
+}
+
+impl Default for RunControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunControl {
+    pub fn new() -> Self {
+        let (reload_tx, reload_rx) = mpsc::unbounded_channel();
+        Self {
+            shutdown: Shutdown::new(),
+            pause: Pause::new(),
+            target_lost: Pause::new(),
+            stats: Arc::new(RunStats::default()),
+            reload_tx,
+            reload_rx: Arc::new(Mutex::new(Some(reload_rx))),
+        }
+    }
+
+    /// Block while paused or while the target is lost. Returns `false` if a
+    /// shutdown was requested instead.
+    pub async fn wait_while_paused(&self) -> bool {
+        // Both have to be clear at the same time, so look again after each wait
+        while let Some(gate) = [&self.pause, &self.target_lost].into_iter().find(|gate| gate.is_paused()) {
+            tokio::select! {
+                _ = gate.wait_until_resumed() => {}
+                _ = self.shutdown.wait() => break,
+            }
+        }
+        !self.shutdown.is_triggered()
+    }
+
+    pub fn status(&self) -> StatusReport {
+        StatusReport {
+            paused: self.pause.is_paused(),
+            target_lost: self.target_lost.is_paused(),
+            iterations: self.stats.iterations.load(Ordering::Relaxed),
+            keys_sent: self.stats.keys_sent.load(Ordering::Relaxed),
+            errors: self.stats.errors.load(Ordering::Relaxed),
+            target_pid: self.stats.target_pid.load(Ordering::Relaxed),
+            target_pids: self.stats.target_pids.lock().unwrap().clone(),
+        }
+    }
+
+    /// Ask [`run_with_reload`] to load the configuration again.
+    ///
+    /// Resolves with the outcome, or an error if nothing is handling reloads.
+    pub async fn request_reload(&self) -> Result<(), String> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.reload_tx
+            .send(reply_tx)
+            .map_err(|_| "reload is not available".to_string())?;
+        reply_rx.await.unwrap_or_else(|_| Err("reload is not available".to_string()))
+    }
+
+    /// A control for one run of the loops: shares pause and stats with `self`
+    /// but can be stopped on its own
+    fn for_run(&self) -> Self {
+        Self {
+            shutdown: Shutdown::new(),
+            ..self.clone()
+        }
+    }
+
+    fn record_send(&self, result: &Result<()>) {
+        let counter = if result.is_ok() { &self.stats.keys_sent } else { &self.stats.errors };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Run whichever mode `config` selects against `targets`.
+///
+/// Any key still held down when the loops end is released, however they ended.
+pub async fn run_configured(
+    config: &Config,
+    key_sender: &KeySender,
+    targets: &Targets,
+    control: &RunControl,
+) -> Result<()> {
+    // Set once, so runs for several targets don't reseed each other
+    key_sender.set_timing(Timing::from_config(config));
+
+    let result = if config.target_mode == TargetMode::All && config.broadcast == Broadcast::Independent {
+        run_each_target(config, key_sender, targets, control).await
+    } else {
+        run_loops(config, key_sender, targets, control).await
+    };
+
+    if let Err(e) = key_sender.release_held_keys() {
+        eprintln!("{} Failed to release held keys: {}", "✗".red(), e);
+    }
+
+    result
+}
+
+/// The independent keys or the key sequence, sending every step to every target
+async fn run_loops(config: &Config, key_sender: &KeySender, targets: &Targets, control: &RunControl) -> Result<()> {
+    if !config.independent_keys.is_empty() {
+        independent_keys_loop(config, key_sender, targets, control).await
+    } else {
+        key_sequence_loop(config, key_sender, targets, control).await
+    }
+}
+
+/// Give every target its own run of the loops: processes that appear get a
+/// new run, those that disappear have theirs stopped.
+///
+/// Ends on shutdown, or once every run has finished by itself.
+async fn run_each_target(config: &Config, key_sender: &KeySender, targets: &Targets, control: &RunControl) -> Result<()> {
+    let mut changes = targets.subscribe();
+    let mut runs: HashMap<u64, Shutdown> = HashMap::new();
+    // Targets whose run is done; they get no new one while the process lives
+    let mut finished: HashSet<u64> = HashSet::new();
+    let mut tasks = JoinSet::new();
+
+    loop {
+        let current = changes.borrow_and_update().clone();
+
+        runs.retain(|window_id, shutdown| {
+            let keep = current.contains(window_id);
+            if !keep {
+                shutdown.trigger();
+            }
+            keep
+        });
+        finished.retain(|window_id| current.contains(window_id));
+
+        for &window_id in &current {
+            if runs.contains_key(&window_id) || finished.contains(&window_id) {
+                continue;
+            }
+            let run = control.for_run();
+            runs.insert(window_id, run.shutdown.clone());
+
+            let config = config.clone();
+            let key_sender = key_sender.clone();
+            tasks.spawn(async move {
+                let result = run_loops(&config, &key_sender, &Targets::from(window_id), &run).await;
+                if let Err(e) = key_sender.release_held_keys_on(window_id) {
+                    eprintln!("{} Failed to release held keys on {}: {}", "✗".red(), window_id, e);
+                }
+                (window_id, result)
+            });
+        }
+
+        tokio::select! {
+            _ = control.shutdown.wait() => break,
+            Ok(()) = changes.changed() => {}
+            Some(joined) = tasks.join_next() => {
+                let (window_id, result) = joined?;
+                if let Err(e) = result {
+                    eprintln!("{} Automation of {} failed: {}", "✗".red(), window_id, e);
+                }
+                // Runs that were stopped are no longer in `runs`
+                if runs.remove(&window_id).is_some() {
+                    finished.insert(window_id);
+                    if runs.is_empty() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    for shutdown in runs.values() {
+        shutdown.trigger();
+    }
+    while let Some(joined) = tasks.join_next().await {
+        let (window_id, result) = joined?;
+        if let Err(e) = result {
+            eprintln!("{} Automation of {} failed: {}", "✗".red(), window_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Run `config` like [`run_configured`], restarting with a fresh config from
+/// `load_config` whenever a reload is requested through `control`.
+///
+/// The targets are kept. If `load_config` fails the old config keeps
+/// running and the error is passed back to whoever asked for the reload.
+pub async fn run_with_reload<F>(
+    mut config: Config,
+    key_sender: &KeySender,
+    targets: &Targets,
+    control: &RunControl,
+    load_config: F,
+) -> Result<()>
+where
+    F: Fn() -> Result<Config>,
+{
+    let Some(mut reload_requests) = control.reload_rx.lock().unwrap().take() else {
+        anyhow::bail!("run_with_reload is already running for this control");
+    };
+
+    loop {
+        let new_config = {
+            let run = control.for_run();
+            let loops = run_configured(&config, key_sender, targets, &run);
+            tokio::pin!(loops);
+
+            let new_config = loop {
+                tokio::select! {
+                    result = &mut loops => return result,
+                    _ = control.shutdown.wait() => {
+                        run.shutdown.trigger();
+                        return loops.await;
+                    }
+                    Some(reply) = reload_requests.recv() => match load_config() {
+                        Ok(new_config) => {
+                            let _ = reply.send(Ok(()));
+                            break new_config;
+                        }
+                        Err(e) => {
+                            let _ = reply.send(Err(e.to_string()));
+                        }
+                    },
+                }
+            };
+
+            // Stop the old loops before starting the new ones
+            run.shutdown.trigger();
+            loops.await?;
+            new_config
+        };
+
+        println!("{} Configuration reloaded", "🔄".green());
+        config = new_config;
+    }
+}
+
+/// Send every independent key on its own timer until shutdown is triggered
+pub async fn run_independent_keys(
+    config: &Config,
+    key_sender: &KeySender,
+    window_id: u64,
+    control: &RunControl,
+) -> Result<()> {
+    key_sender.set_timing(Timing::from_config(config));
+    independent_keys_loop(config, key_sender, &Targets::from(window_id), control).await
+}
+
+async fn independent_keys_loop(
+    config: &Config,
+    key_sender: &KeySender,
+    targets: &Targets,
+    control: &RunControl,
+) -> Result<()> {
+    println!("{} Starting independent keys automation...", "🚀".green());
+
+    let timing = Timing::from_config(config);
+
+    let mut handles = Vec::new();
+
+    for (i, independent_key) in config.independent_keys.iter().enumerate() {
+        let key = independent_key.key.clone();
+        let interval = independent_key.interval;
+        let sender = key_sender.clone();
+        // Each key draws from its own generator, whichever task runs first
+        let timing = timing.for_stream(i as u64);
+        let targets = targets.clone();
+        let verbose = config.verbose;
+        let control = control.clone();
+
+        let handle = tokio::spawn(async move {
+            while control.wait_while_paused().await {
+                // Nothing to send to while the target is not running
+                let windows = targets.live();
+                if !windows.is_empty() {
+                    let result = on_each(&windows, |wid| sender.send_key_to_window(wid, &key));
+                    control.record_send(&result);
+
+                    match result {
+                        Ok(_) => {
+                            if verbose {
+                                println!("✓ Sent key: {}", key.cyan());
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("{} Error sending key '{}': {}", "✗".red(), key, e);
+                        }
+                    }
+                }
+
+                tokio::select! {
+                    _ = sleep(timing.sample(interval)) => {}
+                    _ = control.shutdown.wait() => break,
+                }
+            }
+        });
+
+        handles.push(handle);
+    }
+
+    control.shutdown.wait().await;
+
+    // Let each task finish the key it may be sending
+    for handle in handles {
+        let _ = handle.await;
+    }
+
+    Ok(())
+}
+
+/// Send the key sequence in order, honouring `loop_sequence` and `repeat_count`
+pub async fn run_key_sequence(
+    config: &Config,
+    key_sender: &KeySender,
+    window_id: u64,
+    control: &RunControl,
+) -> Result<()> {
+    key_sender.set_timing(Timing::from_config(config));
+    key_sequence_loop(config, key_sender, &Targets::from(window_id), control).await
+}
+
+async fn key_sequence_loop(
+    config: &Config,
+    key_sender: &KeySender,
+    targets: &Targets,
+    control: &RunControl,
+) -> Result<()> {
+    println!("{} Starting key sequence automation...", "🚀".green());
+
+    let timing = Timing::from_config(config);
+    let mut iteration = 0u32;
+
+    loop {
+        iteration += 1;
+
+        if config.verbose {
+            println!("--- Sequence iteration {} ---", iteration.to_string().cyan());
+        }
+
+        for (i, key_action) in config.key_sequence.iter().enumerate() {
+            // Check if we should stop, holding here while paused
+            if !control.wait_while_paused().await {
+                return Ok(());
+            }
+
+            // Nothing to send to while the target is not running
+            let windows = targets.live();
+            if !windows.is_empty() {
+                let result = perform(key_action, key_sender, &windows, control, &timing).await;
+                control.record_send(&result);
+
+                match result {
+                    Ok(_) => {
+                        if config.verbose {
+                            println!("  {}. ✓ Sent: {}", i + 1, key_action.action.to_string().cyan());
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("  {}. {} Error sending {}: {}", i + 1, "✗".red(), key_action.action, e);
+
+                        // Later steps may never release what earlier ones pressed
+                        if key_sender.held_keys().iter().any(|(window_id, _)| windows.contains(window_id)) {
+                            eprintln!("  {} Releasing held keys", "⚠".yellow());
+                            if let Err(e) = on_each(&windows, |wid| key_sender.release_held_keys_on(wid)) {
+                                eprintln!("  {} Failed to release held keys: {}", "✗".red(), e);
+                            }
+                        }
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = sleep(timing.sample(key_action.interval_after)) => {}
+                _ = control.shutdown.wait() => return Ok(()),
+            }
+        }
+
+        control.stats.iterations.fetch_add(1, Ordering::Relaxed);
+
+        // Check repeat count
+        if config.repeat_count > 0 && iteration >= config.repeat_count {
+            println!("{} Completed {} iterations", "✓".green(), config.repeat_count.to_string().cyan());
+            break;
+        }
+
+        // Check if we should loop
+        if !config.loop_sequence {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Carry out one `key_sequence` step on every window in `windows`
+async fn perform(
+    key_action: &KeyAction,
+    key_sender: &KeySender,
+    windows: &[u64],
+    control: &RunControl,
+    timing: &Timing,
+) -> Result<()> {
+    match &key_action.action {
+        Action::Key(key) => on_each(windows, |wid| key_sender.send_key_to_window(wid, key)),
+        Action::Text(text) => {
+            for (i, c) in text.chars().enumerate() {
+                let char_delay = key_action.char_delay.map(|delay| timing.sample(delay)).unwrap_or_default();
+                if i > 0 && !char_delay.is_zero() {
+                    tokio::select! {
+                        _ = sleep(char_delay) => {}
+                        // Don't keep typing a long string after a stop
+                        _ = control.shutdown.wait() => return Ok(()),
+                    }
+                }
+                on_each(windows, |wid| key_sender.type_char(wid, c))?;
+            }
+
+            Ok(())
+        }
+        Action::Press(key) => on_each(windows, |wid| key_sender.press_keys(wid, key)),
+        Action::Release(key) => on_each(windows, |wid| key_sender.release_keys(wid, key)),
+        Action::Hold { key, duration } => {
+            on_each(windows, |wid| key_sender.press_keys(wid, key))?;
+
+            // A stop cuts the hold short, the keys are still released
+            tokio::select! {
+                _ = sleep(timing.sample(*duration)) => {}
+                _ = control.shutdown.wait() => {}
+            }
+
+            on_each(windows, |wid| key_sender.release_keys(wid, key))
+        }
+        Action::Move(motion) => on_each(windows, |wid| key_sender.move_mouse(wid, *motion)),
+        Action::Click(button) => on_each(windows, |wid| key_sender.click(wid, *button)),
+        Action::MousePress(button) => on_each(windows, |wid| key_sender.press_button(wid, *button)),
+        Action::MouseRelease(button) => on_each(windows, |wid| key_sender.release_button(wid, *button)),
+        Action::Scroll { dx, dy } => on_each(windows, |wid| key_sender.scroll(wid, *dx, *dy)),
+    }
+}
+
+/// Run `send` for every window, trying them all even if one fails; the first error is returned
+fn on_each(windows: &[u64], mut send: impl FnMut(u64) -> Result<()>) -> Result<()> {
+    let mut first_error = None;
+    for &window_id in windows {
+        if let Err(e) = send(window_id) {
+            first_error.get_or_insert(e);
+        }
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
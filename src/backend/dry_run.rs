@@ -0,0 +1,103 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::time::{Duration, Instant};
+
+use super::{BackendKind, InputBackend, MouseButton, MouseMotion};
+
+/// Backend used by `--dry-run`: prints each keystroke instead of sending it.
+///
+/// Keys and mouse actions are still checked against the backend the run
+/// would otherwise use, without connecting to it.
+pub struct DryRunBackend {
+    start: Instant,
+    checks: BackendKind,
+}
+
+impl DryRunBackend {
+    /// Print keystrokes, accepting only what `kind` could send
+    pub fn new(kind: BackendKind) -> Self {
+        Self {
+            start: Instant::now(),
+            checks: kind.resolve(),
+        }
+    }
+
+    /// The backend keys are checked against
+    pub fn checks(&self) -> BackendKind {
+        self.checks
+    }
+
+    fn print(&self, target: u64, what: &str) {
+        println!(
+            "{} [{:>9.3}s] {} → target {}",
+            "🧪".blue(),
+            self.start.elapsed().as_secs_f64(),
+            what.cyan(),
+            target.to_string().yellow()
+        );
+    }
+}
+
+impl InputBackend for DryRunBackend {
+    fn name(&self) -> &'static str {
+        "dry-run"
+    }
+
+    fn validate_key(&self, key: &str) -> Result<()> {
+        self.checks.check_key(key)
+    }
+
+    fn press(&self, target: u64, key: &str) -> Result<()> {
+        self.print(target, &format!("press {}", key));
+        Ok(())
+    }
+
+    fn release(&self, target: u64, key: &str) -> Result<()> {
+        self.print(target, &format!("release {}", key));
+        Ok(())
+    }
+
+    fn tap(&self, target: u64, key: &str, _hold: Duration) -> Result<()> {
+        self.print(target, key);
+        Ok(())
+    }
+
+    fn type_char(&self, target: u64, c: char, _hold: Duration) -> Result<()> {
+        self.print(target, &format!("type '{}'", c.escape_default()));
+        Ok(())
+    }
+
+    fn validate_unicode(&self, _c: char) -> Result<()> {
+        Ok(())
+    }
+
+    fn validate_mouse(&self, absolute: bool) -> Result<()> {
+        self.checks.check_mouse(absolute)
+    }
+
+    fn mouse_move(&self, target: u64, motion: MouseMotion) -> Result<()> {
+        self.print(target, &format!("move mouse {}", motion));
+        Ok(())
+    }
+
+    fn mouse_button(&self, target: u64, button: MouseButton, pressed: bool) -> Result<()> {
+        let action = if pressed { "press" } else { "release" };
+        self.print(target, &format!("{} mouse {}", action, button));
+        Ok(())
+    }
+
+    fn click(&self, target: u64, button: MouseButton, _hold: Duration) -> Result<()> {
+        self.print(target, &format!("click mouse {}", button));
+        Ok(())
+    }
+
+    fn scroll(&self, target: u64, dx: i32, dy: i32) -> Result<()> {
+        self.print(target, &format!("scroll {},{}", dx, dy));
+        Ok(())
+    }
+
+    fn chord(&self, target: u64, keys: &[&str], _hold: Duration) -> Result<()> {
+        self.print(target, &keys.join("+"));
+        Ok(())
+    }
+}
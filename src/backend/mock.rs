@@ -0,0 +1,183 @@
+use anyhow::Result;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{type_layout_char, InputBackend, MouseButton, MouseMotion};
+use crate::layout::Layout;
+
+/// Whether a recorded event pressed or released its key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEventKind {
+    Press,
+    Release,
+    /// Pointer motion; the key is e.g. `"to 10,20"` or `"by 5,-5"`
+    Move,
+    /// Wheel notches; the key is `"dx,dy"`
+    Scroll,
+}
+
+impl KeyEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyEventKind::Press => "press",
+            KeyEventKind::Release => "release",
+            KeyEventKind::Move => "move",
+            KeyEventKind::Scroll => "scroll",
+        }
+    }
+}
+
+/// One key event captured by [`MockBackend`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedEvent {
+    /// Time since the backend was created
+    pub at: Duration,
+    pub target: u64,
+    pub key: String,
+    pub kind: KeyEventKind,
+}
+
+/// Backend that records events instead of sending them.
+///
+/// Use [`events`](Self::events) to inspect the stream in tests. When created
+/// with [`with_output`](Self::with_output) every event is also appended to a
+/// file as one JSON object per line.
+pub struct MockBackend {
+    start: Instant,
+    events: Mutex<Vec<RecordedEvent>>,
+    output: Option<Mutex<File>>,
+    layout: Layout,
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            events: Mutex::new(Vec::new()),
+            output: None,
+            layout: Layout::Us,
+        }
+    }
+
+    /// Record keys as physical positions on `layout`, like `uinput` sends them
+    pub fn with_layout(self, layout: Layout) -> Self {
+        Self { layout, ..self }
+    }
+
+    /// Record events and also write them to `path`, truncating it first
+    pub fn with_output(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .map_err(|e| anyhow::anyhow!("Failed to create mock output file '{}': {}", path.display(), e))?;
+
+        Ok(Self {
+            output: Some(Mutex::new(file)),
+            ..Self::new()
+        })
+    }
+
+    /// Every event recorded so far
+    pub fn events(&self) -> Vec<RecordedEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// `(key, kind)` pairs without timing, convenient for assertions
+    pub fn key_stream(&self) -> Vec<(String, KeyEventKind)> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| (event.key.clone(), event.kind))
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.events.lock().unwrap().clear();
+    }
+
+    fn record(&self, target: u64, key: &str, kind: KeyEventKind) -> Result<()> {
+        let event = RecordedEvent {
+            at: self.start.elapsed(),
+            target,
+            key: key.to_lowercase(),
+            kind,
+        };
+
+        if let Some(output) = &self.output {
+            let line = serde_json::json!({
+                "at_us": event.at.as_micros() as u64,
+                "target": event.target,
+                "key": event.key,
+                "event": event.kind.as_str(),
+            });
+            writeln!(output.lock().unwrap(), "{}", line)
+                .map_err(|e| anyhow::anyhow!("Failed to write mock output: {}", e))?;
+        }
+
+        self.events.lock().unwrap().push(event);
+        Ok(())
+    }
+}
+
+impl InputBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn validate_key(&self, key: &str) -> Result<()> {
+        if key.trim().is_empty() {
+            anyhow::bail!("Key cannot be empty");
+        }
+        Ok(())
+    }
+
+    fn press(&self, target: u64, key: &str) -> Result<()> {
+        self.record(target, key, KeyEventKind::Press)
+    }
+
+    fn release(&self, target: u64, key: &str) -> Result<()> {
+        self.record(target, key, KeyEventKind::Release)
+    }
+
+    fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    fn validate_mouse(&self, _absolute: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn mouse_move(&self, target: u64, motion: MouseMotion) -> Result<()> {
+        self.record(target, &motion.to_string(), KeyEventKind::Move)
+    }
+
+    /// Buttons are recorded as keys named `mouse_left`, `mouse_right` and `mouse_middle`
+    fn mouse_button(&self, target: u64, button: MouseButton, pressed: bool) -> Result<()> {
+        let kind = if pressed { KeyEventKind::Press } else { KeyEventKind::Release };
+        self.record(target, &format!("mouse_{}", button), kind)
+    }
+
+    fn scroll(&self, target: u64, dx: i32, dy: i32) -> Result<()> {
+        self.record(target, &format!("{},{}", dx, dy), KeyEventKind::Scroll)
+    }
+
+    /// Characters without a key on the layout are recorded as themselves
+    fn type_char(&self, target: u64, c: char, hold: Duration) -> Result<()> {
+        type_layout_char(self, target, c, hold).unwrap_or_else(|| {
+            self.record(target, &c.to_string(), KeyEventKind::Press)?;
+            self.record(target, &c.to_string(), KeyEventKind::Release)
+        })
+    }
+
+    fn validate_unicode(&self, _c: char) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,598 @@
+//! Input backends used by [`KeySender`](crate::KeySender) to inject keystrokes.
+//!
+//! A backend knows how to press and release individual keys on one platform
+//! (or one display server). The scheduling code only talks to [`KeySender`],
+//! which parses key strings and delegates to whichever backend is selected.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::keys::{self, RawKey};
+use crate::layout::Layout;
+
+mod dry_run;
+pub mod mock;
+#[cfg(windows)]
+mod sendinput;
+
+#[cfg(target_os = "linux")]
+pub mod uinput;
+#[cfg(target_os = "linux")]
+pub(crate) mod x11;
+
+pub use dry_run::DryRunBackend;
+pub use mock::MockBackend;
+#[cfg(windows)]
+pub use sendinput::SendInputBackend;
+#[cfg(target_os = "linux")]
+pub use uinput::UinputBackend;
+#[cfg(target_os = "linux")]
+pub use x11::{XSendEventBackend, XTestBackend};
+
+/// Highest code a raw `evdev:` key may use; above it are the `BTN_*` codes,
+/// which would make the uinput device look like a mouse or joystick
+pub(crate) const RAW_EVDEV_MAX: u16 = 0xff;
+
+/// How long a key is held down during a tap unless `hold_time` says otherwise
+pub const DEFAULT_HOLD: Duration = Duration::from_millis(30);
+
+/// A keystroke injection mechanism.
+///
+/// `target` is the window ID returned by
+/// [`ProcessFinder::find_process_window`](crate::ProcessFinder::find_process_window);
+/// backends that cannot direct input at a specific window may ignore it.
+/// Keys are single key names such as `"a"`, `"f5"` or `"ctrl"`.
+pub trait InputBackend: Send + Sync {
+    /// Short name used in log output
+    fn name(&self) -> &'static str;
+
+    /// Check that this backend can send `key`
+    fn validate_key(&self, key: &str) -> Result<()>;
+
+    /// Press `key` down without releasing it
+    fn press(&self, target: u64, key: &str) -> Result<()>;
+
+    /// Release a previously pressed `key`
+    fn release(&self, target: u64, key: &str) -> Result<()>;
+
+    /// Press `key`, keep it down for `hold`, then release it
+    fn tap(&self, target: u64, key: &str, hold: Duration) -> Result<()> {
+        self.press(target, key)?;
+        std::thread::sleep(hold);
+        self.release(target, key)
+    }
+
+    /// Check how well keys are expected to reach `target`, one entry per window.
+    ///
+    /// Backends that always deliver (or cannot tell) return an empty list.
+    fn delivery_report(&self, _target: u64) -> Result<Vec<DeliveryReport>> {
+        Ok(Vec::new())
+    }
+
+    /// The layout key names and characters are translated through before
+    /// reaching this backend.
+    ///
+    /// Backends that send physical key positions report the keyboard's
+    /// layout. Those that look keys up on the live mapping translate by
+    /// themselves and keep the default, US, which leaves names unchanged.
+    fn layout(&self) -> Layout {
+        Layout::Us
+    }
+
+    /// Type the character `c`, holding shift or AltGr where the layout needs it
+    /// and keeping its key down for `hold`.
+    ///
+    /// The default types characters of [`layout`](Self::layout) through named
+    /// keys and rejects the rest; backends with a Unicode input method override this.
+    fn type_char(&self, target: u64, c: char, hold: Duration) -> Result<()> {
+        type_layout_char(self, target, c, hold).unwrap_or_else(|| self.validate_unicode(c))
+    }
+
+    /// Check that `c`, which has no key on [`layout`](Self::layout), can be
+    /// typed through a Unicode input method. The default has none; backends
+    /// overriding [`type_char`](Self::type_char) with one override this too.
+    fn validate_unicode(&self, c: char) -> Result<()> {
+        anyhow::bail!(
+            "'{}' has no key on the keyboard layout and the {} backend has no Unicode input method",
+            c.escape_default(),
+            self.name()
+        )
+    }
+
+    /// Check that this backend can send mouse input. `absolute` asks for
+    /// moves to screen coordinates rather than by an offset.
+    fn validate_mouse(&self, _absolute: bool) -> Result<()> {
+        anyhow::bail!("The {} backend can't send mouse input", self.name())
+    }
+
+    /// Move the pointer
+    fn mouse_move(&self, _target: u64, _motion: MouseMotion) -> Result<()> {
+        self.validate_mouse(false)
+    }
+
+    /// Press (`pressed`) or release a mouse button
+    fn mouse_button(&self, _target: u64, _button: MouseButton, _pressed: bool) -> Result<()> {
+        self.validate_mouse(false)
+    }
+
+    /// Press a mouse button, keep it down for `hold`, then release it
+    fn click(&self, target: u64, button: MouseButton, hold: Duration) -> Result<()> {
+        self.mouse_button(target, button, true)?;
+        std::thread::sleep(hold);
+        self.mouse_button(target, button, false)
+    }
+
+    /// Scroll by whole notches; positive `dy` scrolls down, positive `dx` right
+    fn scroll(&self, _target: u64, _dx: i32, _dy: i32) -> Result<()> {
+        self.validate_mouse(false)
+    }
+
+    /// Press `keys` in order, then after `hold` release them in reverse order
+    fn chord(&self, target: u64, keys: &[&str], hold: Duration) -> Result<()> {
+        for (i, key) in keys.iter().enumerate() {
+            if let Err(e) = self.press(target, key) {
+                // Don't leave modifiers stuck if a later key fails
+                for pressed in keys[..i].iter().rev() {
+                    let _ = self.release(target, pressed);
+                }
+                return Err(e);
+            }
+        }
+
+        std::thread::sleep(hold);
+
+        let mut result = Ok(());
+        for key in keys.iter().rev() {
+            if let Err(e) = self.release(target, key) {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+}
+
+/// Type `c` through the named keys that produce it on the backend's layout,
+/// or `None` if it has no key there
+pub(crate) fn type_layout_char<B: InputBackend + ?Sized>(
+    backend: &B,
+    target: u64,
+    c: char,
+    hold: Duration,
+) -> Option<Result<()>> {
+    let chord = backend.layout().char_to_chord(c)?;
+    let keys: Vec<&str> = chord.keys.iter().map(String::as_str).collect();
+
+    Some(match keys[..] {
+        [key] => backend.tap(target, key, hold),
+        _ => backend.chord(target, &keys, hold),
+    })
+}
+
+/// Type `c` as `ctrl+shift+u`, its hex code point and `space`, which GTK and
+/// IBus understand. The digits go through the layout too, since some layouts
+/// only have them on the shift level.
+pub(crate) fn type_unicode_hex<B: InputBackend + ?Sized>(backend: &B, target: u64, c: char, hold: Duration) -> Result<()> {
+    backend.chord(target, &["ctrl", "shift", backend.layout().physical_key("u")], hold)?;
+    for digit in format!("{:x}", c as u32).chars() {
+        type_layout_char(backend, target, digit, hold).unwrap_or_else(|| backend.tap(target, &digit.to_string(), hold))?;
+    }
+    backend.tap(target, "space", hold)
+}
+
+/// A mouse button
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+impl MouseButton {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MouseButton::Left => "left",
+            MouseButton::Right => "right",
+            MouseButton::Middle => "middle",
+        }
+    }
+}
+
+impl fmt::Display for MouseButton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where to move the pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseMotion {
+    /// To screen coordinates
+    To { x: i32, y: i32 },
+    /// By an offset from the current position
+    By { dx: i32, dy: i32 },
+}
+
+impl MouseMotion {
+    pub fn is_absolute(&self) -> bool {
+        matches!(self, MouseMotion::To { .. })
+    }
+}
+
+impl fmt::Display for MouseMotion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MouseMotion::To { x, y } => write!(f, "to {},{}", x, y),
+            MouseMotion::By { dx, dy } => write!(f, "by {},{}", dx, dy),
+        }
+    }
+}
+
+/// Result of [`InputBackend::delivery_report`] for one window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryReport {
+    pub window: u64,
+    pub likely_works: bool,
+    pub detail: String,
+}
+
+/// Which backend to use, as selected by `"backend"` in the config or `--backend`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Pick the best backend for the current platform
+    #[default]
+    Auto,
+    /// Win32 `SendInput` (Windows only)
+    SendInput,
+    /// Virtual keyboard created through `/dev/uinput` (Linux only)
+    Uinput,
+    /// X11 XTEST extension with target window activation (Linux only)
+    Xtest,
+    /// X11 `XSendEvent` straight to the target window, never changes focus (Linux only)
+    XSendEvent,
+    /// Record events instead of sending them (see `mock_output`)
+    Mock,
+}
+
+impl BackendKind {
+    pub const ALL: &'static [BackendKind] = &[
+        BackendKind::Auto,
+        BackendKind::SendInput,
+        BackendKind::Uinput,
+        BackendKind::Xtest,
+        BackendKind::XSendEvent,
+        BackendKind::Mock,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackendKind::Auto => "auto",
+            BackendKind::SendInput => "sendinput",
+            BackendKind::Uinput => "uinput",
+            BackendKind::Xtest => "xtest",
+            BackendKind::XSendEvent => "xsendevent",
+            BackendKind::Mock => "mock",
+        }
+    }
+
+    /// The backend `auto` picks on this machine, guessed without connecting
+    /// to the input system. Other kinds are returned unchanged.
+    pub fn resolve(self) -> BackendKind {
+        match self {
+            BackendKind::Auto if cfg!(windows) => BackendKind::SendInput,
+            BackendKind::Auto if cfg!(target_os = "linux") => {
+                if std::env::var_os("DISPLAY").is_some() {
+                    BackendKind::Xtest
+                } else {
+                    BackendKind::Uinput
+                }
+            }
+            kind => kind,
+        }
+    }
+
+    /// Check that this backend can send `key`, without connecting to it.
+    ///
+    /// The X11 backends can still reject a key that passes when it is not on
+    /// the live keyboard mapping.
+    pub fn check_key(self, key: &str) -> Result<()> {
+        if key.trim().is_empty() {
+            anyhow::bail!("Key cannot be empty");
+        }
+
+        let Ok(raw) = RawKey::parse(key) else {
+            return match self {
+                BackendKind::Mock | BackendKind::Auto => Ok(()),
+                _ if keys::key_def(key).is_some() => Ok(()),
+                _ => anyhow::bail!("Unsupported key: {}", key),
+            };
+        };
+
+        match (self, raw) {
+            (BackendKind::Mock | BackendKind::Auto, _) => Ok(()),
+            (BackendKind::SendInput, RawKey::Vk(_) | RawKey::ScanCode(_)) => Ok(()),
+            (BackendKind::SendInput, _) => anyhow::bail!("The sendinput backend can't send '{}', use a vk: or sc: code", key),
+            (BackendKind::Uinput, RawKey::Evdev(code)) if code <= RAW_EVDEV_MAX => Ok(()),
+            (BackendKind::Uinput, RawKey::Evdev(code)) => {
+                anyhow::bail!("evdev code {} is outside the keyboard range 1-{}", code, RAW_EVDEV_MAX)
+            }
+            (BackendKind::Uinput, _) => anyhow::bail!("The uinput backend can't send '{}', use an evdev: code", key),
+            (BackendKind::Xtest | BackendKind::XSendEvent, RawKey::Keysym(_)) => Ok(()),
+            (BackendKind::Xtest | BackendKind::XSendEvent, _) => {
+                anyhow::bail!("The X11 backends can't send '{}', use a keysym: code", key)
+            }
+        }
+    }
+
+    /// Check that this backend can send mouse input, without connecting to it.
+    /// `absolute` asks for moves to screen coordinates rather than by an offset.
+    pub fn check_mouse(self, absolute: bool) -> Result<()> {
+        match self {
+            BackendKind::Uinput if absolute => {
+                anyhow::bail!("The uinput backend can only move the mouse relatively (use move_by)")
+            }
+            BackendKind::XSendEvent => anyhow::bail!("The xsendevent backend can't send mouse input"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_lowercase();
+        BackendKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = BackendKind::ALL.iter().map(|k| k.as_str()).collect();
+                anyhow::anyhow!("Unknown backend '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Settings shared by all backends
+#[derive(Debug, Clone)]
+pub struct BackendOptions {
+    /// Give focus back to the previously active window after sending
+    pub restore_focus: bool,
+    /// File the mock backend writes its event stream to
+    pub mock_output: Option<PathBuf>,
+    /// Keyboard layout for backends that send physical keys, detected if unset
+    pub layout: Option<Layout>,
+}
+
+impl Default for BackendOptions {
+    fn default() -> Self {
+        Self {
+            restore_focus: true,
+            mock_output: None,
+            layout: None,
+        }
+    }
+}
+
+/// Create the backend for `kind`
+pub fn create_backend(kind: BackendKind, options: &BackendOptions) -> Result<Arc<dyn InputBackend>> {
+    match kind {
+        BackendKind::Auto => create_default_backend(options),
+        BackendKind::SendInput => {
+            #[cfg(windows)]
+            {
+                Ok(Arc::new(SendInputBackend::new(options)))
+            }
+
+            #[cfg(not(windows))]
+            {
+                anyhow::bail!("The sendinput backend is only available on Windows")
+            }
+        }
+        BackendKind::Uinput => {
+            #[cfg(target_os = "linux")]
+            {
+                Ok(Arc::new(UinputBackend::new(options)?))
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            {
+                anyhow::bail!("The uinput backend is only available on Linux")
+            }
+        }
+        BackendKind::Xtest => {
+            #[cfg(target_os = "linux")]
+            {
+                Ok(Arc::new(XTestBackend::new(options)?))
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            {
+                anyhow::bail!("The xtest backend is only available on Linux")
+            }
+        }
+        BackendKind::XSendEvent => {
+            #[cfg(target_os = "linux")]
+            {
+                Ok(Arc::new(XSendEventBackend::new(options)?))
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            {
+                anyhow::bail!("The xsendevent backend is only available on Linux")
+            }
+        }
+        BackendKind::Mock => {
+            // The mock never guesses, so its event stream doesn't depend on the machine
+            let mock = match &options.mock_output {
+                Some(path) => MockBackend::with_output(path)?,
+                None => MockBackend::new(),
+            };
+            Ok(Arc::new(mock.with_layout(options.layout.unwrap_or_default())))
+        }
+    }
+}
+
+#[cfg(windows)]
+fn create_default_backend(options: &BackendOptions) -> Result<Arc<dyn InputBackend>> {
+    Ok(Arc::new(SendInputBackend::new(options)))
+}
+
+#[cfg(target_os = "linux")]
+fn create_default_backend(options: &BackendOptions) -> Result<Arc<dyn InputBackend>> {
+    // XTest can target a window; uinput only reaches whatever has focus
+    if std::env::var_os("DISPLAY").is_some() {
+        if let Ok(backend) = XTestBackend::new(options) {
+            return Ok(Arc::new(backend));
+        }
+    }
+
+    Ok(Arc::new(UinputBackend::new(options)?))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn create_default_backend(_options: &BackendOptions) -> Result<Arc<dyn InputBackend>> {
+    Ok(Arc::new(UnsupportedBackend))
+}
+
+/// Placeholder for platforms without a native backend
+#[cfg(not(any(windows, target_os = "linux")))]
+struct UnsupportedBackend;
+
+#[cfg(not(any(windows, target_os = "linux")))]
+impl InputBackend for UnsupportedBackend {
+    fn name(&self) -> &'static str {
+        "unsupported"
+    }
+
+    fn validate_key(&self, key: &str) -> Result<()> {
+        if key.trim().is_empty() {
+            anyhow::bail!("Key cannot be empty");
+        }
+        Ok(())
+    }
+
+    fn press(&self, _target: u64, _key: &str) -> Result<()> {
+        anyhow::bail!("Unix key sending not yet implemented")
+    }
+
+    fn release(&self, _target: u64, _key: &str) -> Result<()> {
+        anyhow::bail!("Unix key sending not yet implemented")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+        fail_on: Option<&'static str>,
+        layout: Layout,
+    }
+
+    impl InputBackend for Recorder {
+        fn name(&self) -> &'static str {
+            "recorder"
+        }
+
+        fn validate_key(&self, _key: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn press(&self, _target: u64, key: &str) -> Result<()> {
+            if self.fail_on == Some(key) {
+                anyhow::bail!("cannot press {}", key);
+            }
+            self.events.lock().unwrap().push(format!("+{}", key));
+            Ok(())
+        }
+
+        fn release(&self, _target: u64, key: &str) -> Result<()> {
+            self.events.lock().unwrap().push(format!("-{}", key));
+            Ok(())
+        }
+
+        fn layout(&self) -> Layout {
+            self.layout
+        }
+    }
+
+    #[test]
+    fn test_default_type_char() {
+        let recorder = Recorder::default();
+        recorder.type_char(1, 'a', DEFAULT_HOLD).unwrap();
+        recorder.type_char(1, '?', DEFAULT_HOLD).unwrap();
+        assert_eq!(*recorder.events.lock().unwrap(), ["+a", "-a", "+shift", "+slash", "-slash", "-shift"]);
+
+        assert!(recorder.type_char(1, 'é', DEFAULT_HOLD).is_err());
+
+        // Validation sees the same characters typing would
+        let key_sender = crate::KeySender::with_backend(Arc::new(Recorder::default()));
+        assert!(key_sender.parse_key_for_validation("'a?'").is_ok());
+        let error = key_sender.parse_key_for_validation("'aé'").unwrap_err();
+        assert!(error.to_string().starts_with("'\\u{e9}' has no key on the keyboard layout"), "{}", error);
+    }
+
+    #[test]
+    fn test_type_unicode_hex_follows_layout() {
+        let recorder = Recorder { layout: Layout::Fr, ..Recorder::default() };
+        type_unicode_hex(&recorder, 1, 'ω', DEFAULT_HOLD).unwrap();
+        assert_eq!(*recorder.events.lock().unwrap(), [
+            "+ctrl", "+shift", "+u", "-u", "-shift", "-ctrl",
+            // 3c9, with the digits on AZERTY's shift level
+            "+shift", "+3", "-3", "-shift",
+            "+c", "-c",
+            "+shift", "+9", "-9", "-shift",
+            "+space", "-space",
+        ]);
+    }
+
+    #[test]
+    fn test_backend_kind_parsing() {
+        assert_eq!("auto".parse::<BackendKind>().unwrap(), BackendKind::Auto);
+        assert_eq!(" SendInput ".parse::<BackendKind>().unwrap(), BackendKind::SendInput);
+        assert!("nope".parse::<BackendKind>().is_err());
+
+        for kind in BackendKind::ALL {
+            assert_eq!(kind.to_string().parse::<BackendKind>().unwrap(), *kind);
+        }
+    }
+
+    #[test]
+    fn test_default_chord_releases_in_reverse() {
+        let backend = Recorder::default();
+        backend.chord(0, &["ctrl", "shift", "s"], DEFAULT_HOLD).unwrap();
+        assert_eq!(
+            *backend.events.lock().unwrap(),
+            vec!["+ctrl", "+shift", "+s", "-s", "-shift", "-ctrl"]
+        );
+    }
+
+    #[test]
+    fn test_default_chord_releases_on_failure() {
+        let backend = Recorder {
+            fail_on: Some("s"),
+            ..Default::default()
+        };
+        assert!(backend.chord(0, &["ctrl", "shift", "s"], DEFAULT_HOLD).is_err());
+        assert_eq!(
+            *backend.events.lock().unwrap(),
+            vec!["+ctrl", "+shift", "-shift", "-ctrl"]
+        );
+    }
+}
@@ -0,0 +1,450 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::{BackendOptions, InputBackend, MouseButton, MouseMotion};
+use crate::keys::{self, RawKey};
+
+use winapi::um::winuser::{
+    VK_SHIFT, VK_CONTROL, VK_MENU, VkKeyScanW,
+    EnumWindows, GetWindowThreadProcessId, IsWindowVisible, GetWindowTextA,
+    SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+    KEYEVENTF_EXTENDEDKEY, KEYEVENTF_SCANCODE,
+    SetForegroundWindow, SetActiveWindow, BringWindowToTop, ShowWindow,
+    SW_RESTORE, GetForegroundWindow,
+    INPUT_MOUSE, MOUSEINPUT, MOUSEEVENTF_MOVE, MOUSEEVENTF_ABSOLUTE,
+    MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP,
+    MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP, MOUSEEVENTF_WHEEL, MOUSEEVENTF_HWHEEL,
+    WHEEL_DELTA, GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN
+};
+use winapi::shared::windef::HWND;
+
+/// How a key is identified to `SendInput`
+#[derive(Debug, Clone, Copy)]
+enum KeyCode {
+    /// A virtual-key code, translated by the active keyboard layout
+    Vk(u32),
+    /// A raw scan code, `0xE0`-prefixed keys as e.g. `0xE01D`
+    Scan(u16),
+}
+
+/// Win32 `SendInput` backend.
+///
+/// `SendInput` always goes to the foreground window, so the target window is
+/// focused before sending and (optionally) the previous window restored after.
+pub struct SendInputBackend {
+    key_map: HashMap<String, u32>,
+    restore_focus: bool,
+}
+
+impl SendInputBackend {
+    pub fn new(options: &BackendOptions) -> Self {
+        let mut key_map = HashMap::new();
+
+        for def in keys::KEYS {
+            key_map.insert(def.name.to_string(), def.vk as u32);
+            for alias in def.aliases {
+                key_map.insert(alias.to_string(), def.vk as u32);
+            }
+        }
+
+        Self {
+            key_map,
+            restore_focus: options.restore_focus,
+        }
+    }
+
+    fn parse_key(&self, key: &str) -> Result<KeyCode> {
+        if let Ok(raw) = RawKey::parse(key) {
+            return match raw {
+                RawKey::Vk(vk_code) => Ok(KeyCode::Vk(vk_code as u32)),
+                RawKey::ScanCode(scan_code) => Ok(KeyCode::Scan(scan_code)),
+                _ => anyhow::bail!("The sendinput backend can't send '{}', use a vk: or sc: code", key),
+            };
+        }
+
+        // Character keys are whichever key is labelled with them on the active layout
+        if let Some((vk_code, _)) = keys::key_char(key).and_then(vk_for_char) {
+            return Ok(KeyCode::Vk(vk_code));
+        }
+
+        let key_lower = key.to_lowercase();
+
+        // Check map first
+        if let Some(&vk_code) = self.key_map.get(&key_lower) {
+            return Ok(KeyCode::Vk(vk_code));
+        }
+
+        anyhow::bail!("Unsupported key: {}", key)
+    }
+
+    /// Run `send` with the target's window in the foreground.
+    ///
+    /// Falls back to sending globally if the process has no visible window.
+    fn with_target_focus<F>(&self, target: u64, restore: bool, send: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let Some(hwnd) = find_window_by_pid(target as u32) else {
+            return send();
+        };
+
+        // Store current foreground window to restore later
+        let original_window = unsafe { GetForegroundWindow() };
+
+        // Only change focus if the target window is not already focused
+        let needs_focus_change = original_window != hwnd;
+
+        if needs_focus_change {
+            // Bring target window to foreground
+            ensure_window_focus(hwnd);
+        }
+
+        let result = send();
+
+        // Restore original window focus if we changed it
+        if restore && needs_focus_change && !original_window.is_null() {
+            // Small delay to ensure the key is processed
+            std::thread::sleep(std::time::Duration::from_millis(50));
+
+            // Restore focus to original window
+            unsafe {
+                SetForegroundWindow(original_window);
+                SetActiveWindow(original_window);
+            }
+        }
+
+        result
+    }
+}
+
+impl InputBackend for SendInputBackend {
+    fn name(&self) -> &'static str {
+        "sendinput"
+    }
+
+    fn validate_key(&self, key: &str) -> Result<()> {
+        self.parse_key(key).map(|_| ())
+    }
+
+    fn press(&self, target: u64, key: &str) -> Result<()> {
+        let code = self.parse_key(key)?;
+        // Leave the target focused so the matching release reaches it
+        self.with_target_focus(target, false, || send_inputs(key, &[keyboard_input(code, false)]))
+    }
+
+    fn release(&self, target: u64, key: &str) -> Result<()> {
+        let code = self.parse_key(key)?;
+        self.with_target_focus(target, self.restore_focus, || send_inputs(key, &[keyboard_input(code, true)]))
+    }
+
+    fn tap(&self, target: u64, key: &str, hold: Duration) -> Result<()> {
+        let code = self.parse_key(key)?;
+
+        self.with_target_focus(target, self.restore_focus, || {
+            send_inputs(key, &[keyboard_input(code, false)])?;
+
+            // Realistic key press duration
+            std::thread::sleep(hold);
+
+            send_inputs(key, &[keyboard_input(code, true)])
+        })
+    }
+
+    /// Characters are looked up on the active keyboard layout, so the
+    /// `layout` setting isn't needed here. Those not on it are sent as
+    /// `KEYEVENTF_UNICODE` packets. All events go out in one `SendInput`
+    /// call, so `hold` only applies to newlines.
+    fn type_char(&self, target: u64, c: char, hold: Duration) -> Result<()> {
+        // VkKeyScanW maps '\n' to ctrl+enter
+        if c == '\n' {
+            return self.tap(target, "enter", hold);
+        }
+
+        let mut inputs = Vec::new();
+        if let Some((vk_code, shift_state)) = vk_for_char(c) {
+            let modifiers: Vec<u32> = [(1, VK_SHIFT), (2, VK_CONTROL), (4, VK_MENU)]
+                .iter()
+                .filter(|&&(bit, _)| shift_state & bit != 0)
+                .map(|&(_, vk)| vk as u32)
+                .collect();
+
+            for &modifier in &modifiers {
+                inputs.push(keyboard_input(KeyCode::Vk(modifier), false));
+            }
+            inputs.push(keyboard_input(KeyCode::Vk(vk_code), false));
+            inputs.push(keyboard_input(KeyCode::Vk(vk_code), true));
+            for &modifier in modifiers.iter().rev() {
+                inputs.push(keyboard_input(KeyCode::Vk(modifier), true));
+            }
+        } else {
+            let mut units = [0u16; 2];
+            // Characters outside the BMP are sent as a surrogate pair
+            for &unit in c.encode_utf16(&mut units).iter() {
+                inputs.push(unicode_input(unit, false));
+                inputs.push(unicode_input(unit, true));
+            }
+        }
+
+        let what = c.to_string();
+        self.with_target_focus(target, self.restore_focus, || send_inputs(&what, &inputs))
+    }
+
+    fn validate_unicode(&self, _c: char) -> Result<()> {
+        Ok(())
+    }
+
+    fn validate_mouse(&self, _absolute: bool) -> Result<()> {
+        Ok(())
+    }
+
+    /// Relative moves are subject to the user's pointer acceleration settings
+    fn mouse_move(&self, target: u64, motion: MouseMotion) -> Result<()> {
+        let input = match motion {
+            MouseMotion::To { x, y } => {
+                // Absolute coordinates are normalized to 0..=65535 across the primary screen
+                let (width, height) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+                let normalize = |v: i32, size: i32| (v as i64 * 65535 / (size.max(2) - 1) as i64) as i32;
+                mouse_input(
+                    normalize(x, width),
+                    normalize(y, height),
+                    0,
+                    MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE,
+                )
+            }
+            MouseMotion::By { dx, dy } => mouse_input(dx, dy, 0, MOUSEEVENTF_MOVE),
+        };
+
+        let what = format!("mouse {}", motion);
+        self.with_target_focus(target, false, || send_inputs(&what, &[input]))
+    }
+
+    fn mouse_button(&self, target: u64, button: MouseButton, pressed: bool) -> Result<()> {
+        let input = mouse_input(0, 0, 0, button_flag(button, pressed));
+        let restore = !pressed && self.restore_focus;
+        let what = format!("mouse {}", button);
+        self.with_target_focus(target, restore, || send_inputs(&what, &[input]))
+    }
+
+    fn click(&self, target: u64, button: MouseButton, hold: Duration) -> Result<()> {
+        let what = format!("mouse {}", button);
+        self.with_target_focus(target, self.restore_focus, || {
+            send_inputs(&what, &[mouse_input(0, 0, 0, button_flag(button, true))])?;
+            std::thread::sleep(hold);
+            send_inputs(&what, &[mouse_input(0, 0, 0, button_flag(button, false))])
+        })
+    }
+
+    fn scroll(&self, target: u64, dx: i32, dy: i32) -> Result<()> {
+        let delta = WHEEL_DELTA as i32;
+        let mut inputs = Vec::new();
+        // A positive wheel value scrolls up, a positive horizontal one right
+        if dy != 0 {
+            inputs.push(mouse_input(0, 0, (-dy * delta) as u32, MOUSEEVENTF_WHEEL));
+        }
+        if dx != 0 {
+            inputs.push(mouse_input(0, 0, (dx * delta) as u32, MOUSEEVENTF_HWHEEL));
+        }
+
+        self.with_target_focus(target, self.restore_focus, || send_inputs("mouse wheel", &inputs))
+    }
+
+    /// The whole chord goes out in one `SendInput` call, without `hold`
+    fn chord(&self, target: u64, keys: &[&str], _hold: Duration) -> Result<()> {
+        let codes = keys.iter().map(|key| self.parse_key(key)).collect::<Result<Vec<_>>>()?;
+
+        let mut inputs = Vec::with_capacity(codes.len() * 2);
+
+        // Press in order, release in reverse order
+        for &code in &codes {
+            inputs.push(keyboard_input(code, false));
+        }
+        for &code in codes.iter().rev() {
+            inputs.push(keyboard_input(code, true));
+        }
+
+        let combo = keys.join("+");
+        self.with_target_focus(target, self.restore_focus, || send_inputs(&combo, &inputs))
+    }
+}
+
+fn keyboard_input(code: KeyCode, key_up: bool) -> INPUT {
+    let (vk_code, scan_code, mut flags) = match code {
+        KeyCode::Vk(vk_code) => {
+            let flags = if is_extended_vk(vk_code) { KEYEVENTF_EXTENDEDKEY } else { 0 };
+            (vk_code as u16, 0, flags)
+        }
+        KeyCode::Scan(scan_code) => {
+            let flags = if scan_code > 0xff { KEYEVENTF_EXTENDEDKEY } else { 0 };
+            (0, scan_code & 0xff, KEYEVENTF_SCANCODE | flags)
+        }
+    };
+    if key_up {
+        flags |= KEYEVENTF_KEYUP;
+    }
+
+    unsafe {
+        let mut input = INPUT {
+            type_: INPUT_KEYBOARD,
+            u: std::mem::zeroed(),
+        };
+        *input.u.ki_mut() = KEYBDINPUT {
+            wVk: vk_code,
+            wScan: scan_code,
+            dwFlags: flags,
+            time: 0,
+            dwExtraInfo: 0,
+        };
+        input
+    }
+}
+
+/// Keys that share a scan code with another key and are told apart by the
+/// `0xE0` prefix; without the flag `rctrl` arrives as `lctrl`, arrows as numpad keys
+fn is_extended_vk(vk_code: u32) -> bool {
+    matches!(
+        vk_code,
+        0x21..=0x28 // page up/down, end, home, arrows
+            | 0x2C..=0x2E // print screen, insert, delete
+            | 0x5B..=0x5D // left/right windows, menu
+            | 0x6F // numpad divide
+            | 0x90 // num lock
+            | 0xA3 | 0xA5 // right ctrl, right alt
+            | 0xAD..=0xB3 // volume and media keys
+    )
+}
+
+/// The virtual-key code that types `c` on the active keyboard layout, and
+/// the modifiers it needs (bit 0 shift, 1 ctrl, 2 alt; AltGr is ctrl+alt)
+fn vk_for_char(c: char) -> Option<(u32, u8)> {
+    let mut units = [0u16; 2];
+    let [unit] = c.encode_utf16(&mut units) else {
+        return None;
+    };
+
+    let result = unsafe { VkKeyScanW(*unit) } as u16;
+    let (vk_code, shift_state) = ((result & 0xff) as u32, (result >> 8) as u8);
+    if vk_code == 0xff {
+        return None;
+    }
+    Some((vk_code, shift_state))
+}
+
+fn unicode_input(unit: u16, key_up: bool) -> INPUT {
+    unsafe {
+        let mut input = INPUT {
+            type_: INPUT_KEYBOARD,
+            u: std::mem::zeroed(),
+        };
+        *input.u.ki_mut() = KEYBDINPUT {
+            wVk: 0,
+            wScan: unit,
+            dwFlags: KEYEVENTF_UNICODE | if key_up { KEYEVENTF_KEYUP } else { 0 },
+            time: 0,
+            dwExtraInfo: 0,
+        };
+        input
+    }
+}
+
+fn mouse_input(dx: i32, dy: i32, mouse_data: u32, flags: u32) -> INPUT {
+    unsafe {
+        let mut input = INPUT {
+            type_: INPUT_MOUSE,
+            u: std::mem::zeroed(),
+        };
+        *input.u.mi_mut() = MOUSEINPUT {
+            dx,
+            dy,
+            mouseData: mouse_data,
+            dwFlags: flags,
+            time: 0,
+            dwExtraInfo: 0,
+        };
+        input
+    }
+}
+
+fn button_flag(button: MouseButton, pressed: bool) -> u32 {
+    match (button, pressed) {
+        (MouseButton::Left, true) => MOUSEEVENTF_LEFTDOWN,
+        (MouseButton::Left, false) => MOUSEEVENTF_LEFTUP,
+        (MouseButton::Right, true) => MOUSEEVENTF_RIGHTDOWN,
+        (MouseButton::Right, false) => MOUSEEVENTF_RIGHTUP,
+        (MouseButton::Middle, true) => MOUSEEVENTF_MIDDLEDOWN,
+        (MouseButton::Middle, false) => MOUSEEVENTF_MIDDLEUP,
+    }
+}
+
+fn send_inputs(key: &str, inputs: &[INPUT]) -> Result<()> {
+    let mut inputs = inputs.to_vec();
+
+    // Send all inputs at once
+    let result = unsafe {
+        SendInput(
+            inputs.len() as u32,
+            inputs.as_mut_ptr(),
+            std::mem::size_of::<INPUT>() as i32
+        )
+    };
+
+    if result != inputs.len() as u32 {
+        anyhow::bail!("SendInput failed for key '{}' (sent {}/{})", key, result, inputs.len());
+    }
+
+    Ok(())
+}
+
+fn find_window_by_pid(target_pid: u32) -> Option<HWND> {
+    struct EnumData {
+        target_pid: u32,
+        result: Option<HWND>,
+    }
+
+    let mut enum_data = EnumData {
+        target_pid,
+        result: None,
+    };
+
+    unsafe extern "system" fn enum_proc(hwnd: HWND, lparam: isize) -> i32 {
+        let enum_data = unsafe { &mut *(lparam as *mut EnumData) };
+
+        unsafe {
+            let mut window_pid = 0;
+            GetWindowThreadProcessId(hwnd, &mut window_pid);
+
+            if window_pid == enum_data.target_pid && IsWindowVisible(hwnd) != 0 {
+                let mut title = [0u8; 256];
+                let len = GetWindowTextA(hwnd, title.as_mut_ptr() as *mut i8, 256);
+
+                if len > 0 {
+                    enum_data.result = Some(hwnd);
+                    return 0; // Stop enumeration
+                }
+            }
+        }
+
+        1 // Continue enumeration
+    }
+
+    unsafe {
+        EnumWindows(Some(enum_proc), &mut enum_data as *mut _ as isize);
+    }
+
+    enum_data.result
+}
+
+fn ensure_window_focus(hwnd: HWND) {
+    unsafe {
+        // Restore window if minimized
+        ShowWindow(hwnd, SW_RESTORE);
+
+        // Bring to top and set focus
+        BringWindowToTop(hwnd);
+        SetActiveWindow(hwnd);
+        SetForegroundWindow(hwnd);
+
+        // Minimal delay to ensure focus is established
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,248 @@
+use anyhow::Result;
+use colored::Colorize;
+use evdev::uinput::{VirtualDevice, VirtualDeviceBuilder};
+use evdev::{AttributeSet, EventType, InputEvent, Key, RelativeAxisType};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::{
+    type_layout_char, type_unicode_hex, BackendKind, BackendOptions, InputBackend, MouseButton, MouseMotion, RAW_EVDEV_MAX,
+};
+use crate::keys::{self, RawKey};
+use crate::layout::Layout;
+
+/// Name of the virtual keyboard as shown by `evtest` and `/proc/bus/input/devices`
+pub const DEVICE_NAME: &str = "pks virtual keyboard";
+
+/// Time for udev and the display server to pick up a freshly created device;
+/// events emitted before then are silently dropped
+const DEVICE_SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Linux `/dev/uinput` backend.
+///
+/// Creates a virtual keyboard and emits key events from it. The kernel routes
+/// them to whichever window has keyboard focus, so the target is ignored.
+///
+/// The device also has mouse buttons, relative motion and wheels. Like a real
+/// mouse it can't jump to screen coordinates, so `move_to` is not supported.
+pub struct UinputBackend {
+    key_map: HashMap<String, Key>,
+    layout: Layout,
+    device: Mutex<VirtualDevice>,
+}
+
+impl UinputBackend {
+    pub fn new(options: &BackendOptions) -> Result<Self> {
+        let key_map = build_key_map();
+        let layout = options.layout.unwrap_or_else(detect_layout);
+
+        // Every keyboard code, so raw `evdev:` keys get through as well
+        let mut keys = AttributeSet::<Key>::new();
+        for code in 1..=RAW_EVDEV_MAX {
+            keys.insert(Key::new(code));
+        }
+        for button in [MouseButton::Left, MouseButton::Right, MouseButton::Middle] {
+            keys.insert(button_code(button));
+        }
+
+        let mut axes = AttributeSet::<RelativeAxisType>::new();
+        for axis in [
+            RelativeAxisType::REL_X,
+            RelativeAxisType::REL_Y,
+            RelativeAxisType::REL_WHEEL,
+            RelativeAxisType::REL_HWHEEL,
+        ] {
+            axes.insert(axis);
+        }
+
+        let device = VirtualDeviceBuilder::new()
+            .and_then(|builder| builder.name(DEVICE_NAME).with_keys(&keys))
+            .and_then(|builder| builder.with_relative_axes(&axes))
+            .and_then(|builder| builder.build())
+            .map_err(|e| anyhow::anyhow!(
+                "Failed to create uinput device: {} (is the uinput module loaded and /dev/uinput writable?)", e
+            ))?;
+
+        std::thread::sleep(DEVICE_SETTLE_TIME);
+
+        Ok(Self {
+            key_map,
+            layout,
+            device: Mutex::new(device),
+        })
+    }
+
+    fn parse_key(&self, key: &str) -> Result<Key> {
+        if let Ok(raw) = RawKey::parse(key) {
+            return match raw {
+                RawKey::Evdev(code) if code <= RAW_EVDEV_MAX => Ok(Key::new(code)),
+                RawKey::Evdev(code) => anyhow::bail!("evdev code {} is outside the keyboard range 1-{}", code, RAW_EVDEV_MAX),
+                _ => anyhow::bail!("The uinput backend can't send '{}', use an evdev: code", key),
+            };
+        }
+
+        let key_lower = key.to_lowercase();
+
+        if let Some(&code) = self.key_map.get(&key_lower) {
+            return Ok(code);
+        }
+
+        anyhow::bail!("Unsupported key: {}", key)
+    }
+
+    fn emit(&self, key: &str, value: i32) -> Result<()> {
+        let code = self.parse_key(key)?;
+        let event = InputEvent::new(EventType::KEY, code.code(), value);
+
+        // emit() follows the event with a SYN_REPORT
+        self.device
+            .lock()
+            .unwrap()
+            .emit(&[event])
+            .map_err(|e| anyhow::anyhow!("uinput write failed for key '{}': {}", key, e))
+    }
+
+    /// Emit relative axis events, skipping zero values, as one report
+    fn emit_relative(&self, axes: &[(RelativeAxisType, i32)]) -> Result<()> {
+        let events: Vec<InputEvent> = axes
+            .iter()
+            .filter(|(_, value)| *value != 0)
+            .map(|(axis, value)| InputEvent::new(EventType::RELATIVE, axis.0, *value))
+            .collect();
+
+        self.device
+            .lock()
+            .unwrap()
+            .emit(&events)
+            .map_err(|e| anyhow::anyhow!("uinput write failed for mouse event: {}", e))
+    }
+}
+
+/// The session's layout, falling back to US when it can't be used
+fn detect_layout() -> Layout {
+    match Layout::detect() {
+        Some(Ok(layout)) => layout,
+        Some(Err(name)) => {
+            eprintln!(
+                "{} Keyboard layout '{}' is not supported, typing as if it were 'us' (set \"layout\" to override)",
+                "⚠".yellow(),
+                name
+            );
+            Layout::Us
+        }
+        None => Layout::Us,
+    }
+}
+
+fn button_code(button: MouseButton) -> Key {
+    match button {
+        MouseButton::Left => Key::BTN_LEFT,
+        MouseButton::Right => Key::BTN_RIGHT,
+        MouseButton::Middle => Key::BTN_MIDDLE,
+    }
+}
+
+impl InputBackend for UinputBackend {
+    fn name(&self) -> &'static str {
+        "uinput"
+    }
+
+    fn validate_key(&self, key: &str) -> Result<()> {
+        self.parse_key(key).map(|_| ())
+    }
+
+    fn press(&self, _target: u64, key: &str) -> Result<()> {
+        self.emit(key, 1)
+    }
+
+    fn release(&self, _target: u64, key: &str) -> Result<()> {
+        self.emit(key, 0)
+    }
+
+    fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    fn validate_mouse(&self, absolute: bool) -> Result<()> {
+        BackendKind::Uinput.check_mouse(absolute)
+    }
+
+    fn mouse_move(&self, _target: u64, motion: MouseMotion) -> Result<()> {
+        match motion {
+            MouseMotion::By { dx, dy } => {
+                self.emit_relative(&[(RelativeAxisType::REL_X, dx), (RelativeAxisType::REL_Y, dy)])
+            }
+            MouseMotion::To { .. } => self.validate_mouse(true),
+        }
+    }
+
+    fn mouse_button(&self, _target: u64, button: MouseButton, pressed: bool) -> Result<()> {
+        let event = InputEvent::new(EventType::KEY, button_code(button).code(), pressed as i32);
+        self.device
+            .lock()
+            .unwrap()
+            .emit(&[event])
+            .map_err(|e| anyhow::anyhow!("uinput write failed for mouse button '{}': {}", button, e))
+    }
+
+    /// The wheel counts up away from the user, so scrolling down is negative
+    fn scroll(&self, _target: u64, dx: i32, dy: i32) -> Result<()> {
+        self.emit_relative(&[(RelativeAxisType::REL_WHEEL, -dy), (RelativeAxisType::REL_HWHEEL, dx)])
+    }
+
+    /// Characters not on the layout are entered as their hex code point,
+    /// see [`type_unicode_hex`]
+    fn type_char(&self, target: u64, c: char, hold: Duration) -> Result<()> {
+        type_layout_char(self, target, c, hold).unwrap_or_else(|| type_unicode_hex(self, target, c, hold))
+    }
+
+    fn validate_unicode(&self, _c: char) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Every name and alias in the [key table](crate::keys::KEYS) mapped to its evdev key
+pub(crate) fn build_key_map() -> HashMap<String, Key> {
+    let mut key_map = HashMap::new();
+
+    for def in keys::KEYS {
+        let key = Key::new(def.evdev);
+        key_map.insert(def.name.to_string(), key);
+        for alias in def.aliases {
+            key_map.insert(alias.to_string(), key);
+        }
+    }
+
+    key_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_map_matches_evdev_codes() {
+        let key_map = build_key_map();
+        assert_eq!(key_map["a"], Key::KEY_A);
+        assert_eq!(key_map["q"], Key::KEY_Q);
+        assert_eq!(key_map["z"], Key::KEY_Z);
+        assert_eq!(key_map["0"], Key::KEY_0);
+        assert_eq!(key_map["1"], Key::KEY_1);
+        assert_eq!(key_map["f11"], Key::KEY_F11);
+        assert_eq!(key_map["ctrl"], Key::KEY_LEFTCTRL);
+        assert_eq!(key_map["period"], Key::KEY_DOT);
+        assert_eq!(key_map["f13"], Key::KEY_F13);
+        assert_eq!(key_map["f24"], Key::KEY_F24);
+        assert_eq!(key_map["kp0"], Key::KEY_KP0);
+        assert_eq!(key_map["kp7"], Key::KEY_KP7);
+        assert_eq!(key_map["kpenter"], Key::KEY_KPENTER);
+        assert_eq!(key_map["printscreen"], Key::KEY_SYSRQ);
+        assert_eq!(key_map["menu"], Key::KEY_COMPOSE);
+        assert_eq!(key_map["win"], Key::KEY_LEFTMETA);
+        assert_eq!(key_map["capslock"], Key::KEY_CAPSLOCK);
+        assert_eq!(key_map["volumeup"], Key::KEY_VOLUMEUP);
+        assert_eq!(key_map["playpause"], Key::KEY_PLAYPAUSE);
+        assert_eq!(key_map["prevtrack"], Key::KEY_PREVIOUSSONG);
+    }
+}
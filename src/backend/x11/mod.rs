@@ -0,0 +1,357 @@
+//! Shared X11 plumbing for the X11 input backends.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    AtomEnum, ClientMessageEvent, ConnectionExt as _, EventMask, InputFocus, Keycode, Keysym, Window,
+};
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::CURRENT_TIME;
+
+use crate::keys::{self, RawKey};
+
+mod sendevent;
+mod xtest;
+
+pub use sendevent::XSendEventBackend;
+pub use xtest::XTestBackend;
+
+x11rb::atom_manager! {
+    pub(crate) Atoms: AtomsCookie {
+        _NET_WM_PID,
+        _NET_CLIENT_LIST,
+        _NET_ACTIVE_WINDOW,
+        _NET_SUPPORTED,
+    }
+}
+
+/// Time for the window manager to process an activation request
+const FOCUS_SETTLE_TIME: Duration = Duration::from_millis(50);
+
+/// Time for clients to pick up a keyboard mapping change
+const MAPPING_SETTLE_TIME: Duration = Duration::from_millis(20);
+
+/// An X server connection plus the lookups every X11 backend needs
+pub(crate) struct X11Session {
+    pub(crate) conn: RustConnection,
+    pub(crate) root: Window,
+    atoms: Atoms,
+    /// Whether the window manager handles `_NET_ACTIVE_WINDOW` requests
+    ewmh_activation: bool,
+    keyboard: KeyboardMap,
+}
+
+impl X11Session {
+    pub(crate) fn connect() -> Result<Self> {
+        let (conn, screen_num) = x11rb::connect(None)
+            .map_err(|e| anyhow::anyhow!("Failed to connect to the X server: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+        let atoms = Atoms::new(&conn)?.reply()?;
+
+        let supported = conn
+            .get_property(false, root, atoms._NET_SUPPORTED, AtomEnum::ATOM, 0, u32::MAX)?
+            .reply()?;
+        let ewmh_activation = supported
+            .value32()
+            .is_some_and(|mut atoms_list| atoms_list.any(|atom| atom == atoms._NET_ACTIVE_WINDOW));
+
+        let keyboard = load_keyboard_map(&conn)?;
+
+        Ok(Self {
+            conn,
+            root,
+            atoms,
+            ewmh_activation,
+            keyboard,
+        })
+    }
+
+    /// Look up the keycode that produces `key` on the current keyboard mapping,
+    /// which already reflects the session's layout
+    pub(crate) fn keycode(&self, key: &str) -> Result<Keycode> {
+        let keysym = match RawKey::parse(key) {
+            Ok(RawKey::Keysym(keysym)) => keysym,
+            Ok(_) => anyhow::bail!("The X11 backends can't send '{}', use a keysym: code", key),
+            Err(_) => key_name_to_keysym(key).ok_or_else(|| anyhow::anyhow!("Unsupported key: {}", key))?,
+        };
+
+        // A character only on the shift level still names the key labelled with it
+        self.keyboard
+            .unshifted
+            .get(&keysym)
+            .or_else(|| self.keyboard.shifted.get(&keysym))
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Key '{}' is not on the current X keyboard mapping", key))
+    }
+
+    /// Run `send` with the keycode that types `c` and whether shift must be held.
+    ///
+    /// Characters missing from the keyboard mapping are temporarily mapped to
+    /// an unused keycode, which is cleared again once `send` returns.
+    pub(crate) fn with_char_keycode<F>(&self, c: char, send: F) -> Result<()>
+    where
+        F: FnOnce(Keycode, bool) -> Result<()>,
+    {
+        let keysym = char_to_keysym(c);
+
+        if let Some(&keycode) = self.keyboard.unshifted.get(&keysym) {
+            return send(keycode, false);
+        }
+        if let Some(&keycode) = self.keyboard.shifted.get(&keysym) {
+            return send(keycode, true);
+        }
+
+        let spare = self.keyboard.spare.ok_or_else(|| {
+            anyhow::anyhow!("'{}' is not on the X keyboard mapping and there is no free keycode to map it to", c)
+        })?;
+
+        self.conn.change_keyboard_mapping(1, spare, 1, &[keysym])?.check()?;
+        self.conn.sync()?;
+        std::thread::sleep(MAPPING_SETTLE_TIME);
+
+        let result = send(spare, false);
+
+        // Let the target translate the key before its keysym goes away
+        std::thread::sleep(MAPPING_SETTLE_TIME);
+        self.conn.change_keyboard_mapping(1, spare, 1, &[0])?.check()?;
+        result
+    }
+
+    /// Find a top-level window whose `_NET_WM_PID` is `pid`
+    pub(crate) fn find_window_by_pid(&self, pid: u32) -> Result<Option<Window>> {
+        Ok(self.find_windows_by_pid(pid)?.into_iter().next())
+    }
+
+    /// Find every top-level window whose `_NET_WM_PID` is `pid`
+    pub(crate) fn find_windows_by_pid(&self, pid: u32) -> Result<Vec<Window>> {
+        let mut windows = Vec::new();
+
+        // Prefer the window manager's client list, it only holds real top-level windows
+        let client_list = self
+            .conn
+            .get_property(false, self.root, self.atoms._NET_CLIENT_LIST, AtomEnum::WINDOW, 0, u32::MAX)?
+            .reply()?;
+        if let Some(clients) = client_list.value32() {
+            for window in clients {
+                if self.window_pid(window)? == Some(pid) {
+                    windows.push(window);
+                }
+            }
+        }
+
+        if !windows.is_empty() {
+            return Ok(windows);
+        }
+
+        // No EWMH window manager: walk the window tree instead
+        let mut pending = vec![self.root];
+        while let Some(parent) = pending.pop() {
+            for window in self.conn.query_tree(parent)?.reply()?.children {
+                if self.window_pid(window)? == Some(pid) {
+                    windows.push(window);
+                } else {
+                    pending.push(window);
+                }
+            }
+        }
+
+        Ok(windows)
+    }
+
+    fn window_pid(&self, window: Window) -> Result<Option<u32>> {
+        let reply = match self
+            .conn
+            .get_property(false, window, self.atoms._NET_WM_PID, AtomEnum::CARDINAL, 0, 1)?
+            .reply()
+        {
+            Ok(reply) => reply,
+            // The window may have been destroyed while we were looking at it
+            Err(_) => return Ok(None),
+        };
+
+        Ok(reply.value32().and_then(|mut values| values.next()))
+    }
+
+    /// The class part of `WM_CLASS`, e.g. `"XTerm"`
+    pub(crate) fn window_class(&self, window: Window) -> Result<Option<String>> {
+        let reply = self
+            .conn
+            .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)?
+            .reply()?;
+
+        // WM_CLASS holds two NUL-terminated strings: instance, then class
+        Ok(reply
+            .value8()
+            .map(|bytes| bytes.collect::<Vec<u8>>())
+            .and_then(|bytes| {
+                bytes
+                    .split(|&b| b == 0)
+                    .nth(1)
+                    .filter(|class| !class.is_empty())
+                    .map(|class| String::from_utf8_lossy(class).into_owned())
+            }))
+    }
+
+    /// The window that currently has keyboard focus
+    pub(crate) fn active_window(&self) -> Result<Option<Window>> {
+        if self.ewmh_activation {
+            let reply = self
+                .conn
+                .get_property(false, self.root, self.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW, 0, 1)?
+                .reply()?;
+            return Ok(reply.value32().and_then(|mut values| values.next()).filter(|&w| w != 0));
+        }
+
+        // 0 and 1 are the special None and PointerRoot focus values
+        let focus = self.conn.get_input_focus()?.reply()?.focus;
+        Ok(Some(focus).filter(|&w| w > 1 && w != self.root))
+    }
+
+    /// Ask the window manager to activate `window`, or focus it directly without one
+    pub(crate) fn activate(&self, window: Window) -> Result<()> {
+        if self.ewmh_activation {
+            // Source indication 2: request comes from a pager, which WMs honour unconditionally
+            let event = ClientMessageEvent::new(32, window, self.atoms._NET_ACTIVE_WINDOW, [2, CURRENT_TIME, 0, 0, 0]);
+            self.conn.send_event(
+                false,
+                self.root,
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            )?;
+        } else {
+            self.conn.set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)?;
+        }
+
+        self.conn.sync()?;
+        std::thread::sleep(FOCUS_SETTLE_TIME);
+        Ok(())
+    }
+
+    /// Run `send` with `target`'s window active.
+    ///
+    /// If the process has no window, `send` goes to whatever currently has focus.
+    pub(crate) fn with_target_focus<F>(&self, target: u64, restore: bool, send: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let Some(window) = self.find_window_by_pid(target as u32)? else {
+            return send();
+        };
+
+        let original_window = self.active_window()?;
+        let needs_focus_change = original_window != Some(window);
+
+        if needs_focus_change {
+            self.activate(window)?;
+        }
+
+        let result = send();
+
+        if restore && needs_focus_change {
+            if let Some(original) = original_window {
+                // Give the target time to process the key before it loses focus
+                std::thread::sleep(FOCUS_SETTLE_TIME);
+                self.activate(original)?;
+            }
+        }
+
+        result
+    }
+}
+
+/// Keysym -> keycode tables built from the server's keyboard mapping
+#[derive(Default)]
+struct KeyboardMap {
+    /// Keysyms typed without modifiers
+    unshifted: HashMap<Keysym, Keycode>,
+    /// Keysyms typed with shift
+    shifted: HashMap<Keysym, Keycode>,
+    /// A keycode with no keysyms, free to borrow for characters not on the keyboard
+    spare: Option<Keycode>,
+}
+
+/// Read the server's keyboard mapping.
+///
+/// Unshifted and shifted keysyms are kept apart so that e.g. `1` never
+/// resolves to the keycode of a key where `1` is the shifted symbol.
+fn load_keyboard_map(conn: &RustConnection) -> Result<KeyboardMap> {
+    let setup = conn.setup();
+    let min = setup.min_keycode;
+    let count = setup.max_keycode - min + 1;
+    let mapping = conn.get_keyboard_mapping(min, count)?.reply()?;
+
+    let per_keycode = mapping.keysyms_per_keycode as usize;
+    let mut keyboard = KeyboardMap::default();
+
+    if per_keycode == 0 {
+        return Ok(keyboard);
+    }
+
+    for (i, syms) in mapping.keysyms.chunks(per_keycode).enumerate() {
+        let keycode = min + i as u8;
+
+        if syms.iter().all(|&keysym| keysym == 0) {
+            // Prefer the highest free keycode, the low ones belong to real keys
+            keyboard.spare = Some(keycode);
+            continue;
+        }
+
+        if let Some(&keysym) = syms.first().filter(|&&keysym| keysym != 0) {
+            keyboard.unshifted.entry(keysym).or_insert(keycode);
+        }
+        if let Some(&keysym) = syms.get(1).filter(|&&keysym| keysym != 0) {
+            keyboard.shifted.entry(keysym).or_insert(keycode);
+        }
+    }
+
+    Ok(keyboard)
+}
+
+/// The keysym that types `c`: Latin-1 characters are their own keysym, the
+/// rest of Unicode lives at `0x01000000 + code point`
+pub(crate) fn char_to_keysym(c: char) -> Keysym {
+    match c {
+        '\n' => 0xff0d,
+        '\t' => 0xff09,
+        '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => c as u32,
+        _ => 0x0100_0000 + c as u32,
+    }
+}
+
+/// Map a key name to its X keysym from the [key table](crate::keys::KEYS)
+pub(crate) fn key_name_to_keysym(key: &str) -> Option<Keysym> {
+    keys::key_def(key).map(|def| def.keysym)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_name_to_keysym() {
+        assert_eq!(key_name_to_keysym("a"), Some(0x61));
+        assert_eq!(key_name_to_keysym("Z"), Some(0x7a));
+        assert_eq!(key_name_to_keysym("7"), Some(0x37));
+        assert_eq!(key_name_to_keysym("f1"), Some(0xffbe));
+        assert_eq!(key_name_to_keysym("f12"), Some(0xffc9));
+        assert_eq!(key_name_to_keysym("return"), Some(0xff0d));
+        assert_eq!(key_name_to_keysym("f24"), Some(0xffd5));
+        assert_eq!(key_name_to_keysym("f25"), None);
+        assert_eq!(key_name_to_keysym("kp5"), Some(0xffb5));
+        assert_eq!(key_name_to_keysym("volumeup"), Some(0x1008ff13));
+        assert_eq!(key_name_to_keysym("ab"), None);
+        assert_eq!(key_name_to_keysym(""), None);
+        assert_eq!(key_name_to_keysym("period"), Some(0x2e));
+    }
+
+    #[test]
+    fn test_char_to_keysym() {
+        assert_eq!(char_to_keysym('A'), 0x41);
+        assert_eq!(char_to_keysym('é'), 0xe9);
+        assert_eq!(char_to_keysym('€'), 0x0100_20ac);
+        assert_eq!(char_to_keysym('\n'), 0xff0d);
+    }
+}
@@ -0,0 +1,283 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use x11rb::protocol::xproto::{
+    ConnectionExt as _, EventMask, KeyButMask, KeyPressEvent, Keycode, Window, KEY_PRESS_EVENT, KEY_RELEASE_EVENT,
+};
+use x11rb::{CURRENT_TIME, NONE};
+
+use super::X11Session;
+use crate::backend::{BackendOptions, DeliveryReport, InputBackend};
+
+/// Window classes known to drop events with the `send_event` flag set
+const IGNORES_SEND_EVENT: &[(&str, &str)] = &[
+    ("XTerm", "XTerm ignores synthetic events unless allowSendEvents is enabled"),
+    ("UXTerm", "XTerm ignores synthetic events unless allowSendEvents is enabled"),
+];
+
+/// X11 backend that sends synthetic key events straight to the target window.
+///
+/// The active window never changes, so this is safe to use while working in
+/// another application. The catch is that the events carry the `send_event`
+/// flag, which some applications ignore; see
+/// [`delivery_report`](InputBackend::delivery_report).
+pub struct XSendEventBackend {
+    session: X11Session,
+    /// Modifiers currently held through `press`, reported in each event's state
+    modifiers: Mutex<Modifiers>,
+}
+
+impl XSendEventBackend {
+    pub fn new(_options: &BackendOptions) -> Result<Self> {
+        let session = X11Session::connect()?;
+        let mapping = session.conn.get_modifier_mapping()?.reply()?;
+
+        Ok(Self {
+            session,
+            modifiers: Mutex::new(Modifiers::new(&mapping.keycodes)),
+        })
+    }
+
+    /// The window of `target` that key events should be sent to
+    fn key_window(&self, target: u64) -> Result<Window> {
+        let windows = self.session.find_windows_by_pid(target as u32)?;
+
+        for &window in &windows {
+            if let Some(listener) = self.find_key_listener(window)? {
+                return Ok(listener);
+            }
+        }
+
+        // Nobody listens; send to the top-level window and let it propagate
+        windows
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("No X11 window found for process {}", target))
+    }
+
+    /// The first window in `top`'s subtree that some client selected key events on
+    fn find_key_listener(&self, top: Window) -> Result<Option<Window>> {
+        let mut pending = vec![top];
+
+        while let Some(window) = pending.pop() {
+            let attributes = match self.session.conn.get_window_attributes(window)?.reply() {
+                Ok(attributes) => attributes,
+                Err(_) => continue,
+            };
+
+            if attributes.all_event_masks.contains(EventMask::KEY_PRESS) {
+                return Ok(Some(window));
+            }
+
+            if let Ok(tree) = self.session.conn.query_tree(window)?.reply() {
+                pending.extend(tree.children);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn send_keycode_event(&self, target: u64, keycode: Keycode, event_type: u8, state: u16, what: &str) -> Result<()> {
+        let window = self.key_window(target)?;
+
+        let event = KeyPressEvent {
+            response_type: event_type,
+            detail: keycode,
+            sequence: 0,
+            time: CURRENT_TIME,
+            root: self.session.root,
+            event: window,
+            child: NONE,
+            root_x: 1,
+            root_y: 1,
+            event_x: 1,
+            event_y: 1,
+            state: state.into(),
+            same_screen: true,
+        };
+
+        let mask = if event_type == KEY_PRESS_EVENT {
+            EventMask::KEY_PRESS
+        } else {
+            EventMask::KEY_RELEASE
+        };
+
+        self.session
+            .conn
+            .send_event(true, window, mask, event)?
+            .check()
+            .map_err(|e| anyhow::anyhow!("XSendEvent failed for key '{}': {}", what, e))
+    }
+}
+
+impl InputBackend for XSendEventBackend {
+    fn name(&self) -> &'static str {
+        "xsendevent"
+    }
+
+    fn validate_key(&self, key: &str) -> Result<()> {
+        self.session.keycode(key).map(|_| ())
+    }
+
+    fn press(&self, target: u64, key: &str) -> Result<()> {
+        let keycode = self.session.keycode(key)?;
+        let mut modifiers = self.modifiers.lock().unwrap();
+
+        // The state field describes modifiers held *before* this event
+        self.send_keycode_event(target, keycode, KEY_PRESS_EVENT, modifiers.state(), key)?;
+        modifiers.press(keycode);
+        Ok(())
+    }
+
+    fn release(&self, target: u64, key: &str) -> Result<()> {
+        let keycode = self.session.keycode(key)?;
+        let mut modifiers = self.modifiers.lock().unwrap();
+
+        let result = self.send_keycode_event(target, keycode, KEY_RELEASE_EVENT, modifiers.state(), key);
+        modifiers.release(keycode);
+        result
+    }
+
+    /// Shift is only reported in the event state, never actually pressed.
+    /// The window gets press and release together, so `hold` doesn't apply.
+    fn type_char(&self, target: u64, c: char, _hold: Duration) -> Result<()> {
+        let what = c.to_string();
+        let modifiers = self.modifiers.lock().unwrap().state();
+
+        self.session.with_char_keycode(c, |keycode, needs_shift| {
+            let state = if needs_shift { modifiers | u16::from(KeyButMask::SHIFT) } else { modifiers };
+            self.send_keycode_event(target, keycode, KEY_PRESS_EVENT, state, &what)?;
+            self.send_keycode_event(target, keycode, KEY_RELEASE_EVENT, state, &what)
+        })
+    }
+
+    fn validate_unicode(&self, _c: char) -> Result<()> {
+        Ok(())
+    }
+
+    fn delivery_report(&self, target: u64) -> Result<Vec<DeliveryReport>> {
+        let mut reports = Vec::new();
+
+        for window in self.session.find_windows_by_pid(target as u32)? {
+            let class = self.session.window_class(window)?;
+            let listener = self.find_key_listener(window)?;
+
+            let known_issue = class.as_deref().and_then(|class| {
+                IGNORES_SEND_EVENT
+                    .iter()
+                    .find(|(name, _)| *name == class)
+                    .map(|(_, reason)| *reason)
+            });
+
+            let (likely_works, detail) = match (listener, known_issue) {
+                (_, Some(reason)) => (false, reason.to_string()),
+                (Some(listener), None) => (true, format!("key events go to window {:#x}", listener)),
+                (None, None) => (false, "no window of this process listens for key events".to_string()),
+            };
+
+            reports.push(DeliveryReport {
+                window: window as u64,
+                likely_works,
+                detail: match class {
+                    Some(class) => format!("{}: {}", class, detail),
+                    None => detail,
+                },
+            });
+        }
+
+        Ok(reports)
+    }
+}
+
+/// The modifier keys held down, tracked by keycode so releasing one of two
+/// keys for the same modifier (`lshift` and `rshift`) keeps it held
+struct Modifiers {
+    /// The state bit each modifier keycode sets, from the server's mapping
+    bits: HashMap<Keycode, u16>,
+    held: Vec<Keycode>,
+}
+
+impl Modifiers {
+    /// Read a `GetModifierMapping` reply: eight equal groups of keycodes, for
+    /// Shift, Lock, Control and Mod1 to Mod5, with 0 for unused slots
+    fn new(mapping: &[Keycode]) -> Self {
+        let mut bits = HashMap::new();
+        let per_modifier = (mapping.len() / 8).max(1);
+
+        for (index, keycodes) in mapping.chunks(per_modifier).enumerate() {
+            for &keycode in keycodes.iter().filter(|&&keycode| keycode != 0) {
+                *bits.entry(keycode).or_insert(0) |= 1 << index;
+            }
+        }
+
+        Self { bits, held: Vec::new() }
+    }
+
+    /// The state field for an event: the bits of every modifier held
+    fn state(&self) -> u16 {
+        self.held.iter().filter_map(|keycode| self.bits.get(keycode)).fold(0, |state, bit| state | bit)
+    }
+
+    fn press(&mut self, keycode: Keycode) {
+        if self.bits.contains_key(&keycode) {
+            self.held.push(keycode);
+        }
+    }
+
+    fn release(&mut self, keycode: Keycode) {
+        if let Some(index) = self.held.iter().position(|&held| held == keycode) {
+            self.held.remove(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modifiers_follow_the_server_mapping() {
+        // Two keycodes per modifier, as on a typical DE layout where the
+        // right Alt key (108) is ISO_Level3_Shift on Mod5
+        let mapping = [
+            50, 62,   // Shift
+            66, 0,    // Lock
+            37, 105,  // Control
+            64, 204,  // Mod1
+            77, 0,    // Mod2
+            0, 0,     // Mod3
+            133, 134, // Mod4
+            108, 92,  // Mod5
+        ];
+        let mut modifiers = Modifiers::new(&mapping);
+        let bit = |mask: KeyButMask| u16::from(mask);
+
+        let expected = [
+            (62, KeyButMask::SHIFT),
+            (105, KeyButMask::CONTROL),
+            (64, KeyButMask::MOD1),
+            (134, KeyButMask::MOD4),
+            (108, KeyButMask::MOD5),
+        ];
+        for (keycode, mask) in expected {
+            modifiers.press(keycode);
+            assert_eq!(modifiers.state(), bit(mask), "{}", keycode);
+            modifiers.release(keycode);
+        }
+
+        // Keys that aren't modifiers don't change the state
+        modifiers.press(38);
+        assert_eq!(modifiers.state(), 0);
+
+        // Releasing the left shift keeps the right one held
+        modifiers.press(50);
+        modifiers.press(62);
+        modifiers.press(37);
+        modifiers.release(50);
+        assert_eq!(modifiers.state(), bit(KeyButMask::SHIFT) | bit(KeyButMask::CONTROL));
+        modifiers.release(62);
+        modifiers.release(37);
+        assert_eq!(modifiers.state(), 0);
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::Result;
+use std::time::Duration;
+use x11rb::protocol::xproto::{
+    Keycode, BUTTON_PRESS_EVENT, BUTTON_RELEASE_EVENT, KEY_PRESS_EVENT, KEY_RELEASE_EVENT, MOTION_NOTIFY_EVENT,
+};
+use x11rb::protocol::xtest::{self, ConnectionExt as _};
+use x11rb::connection::RequestConnection;
+use x11rb::CURRENT_TIME;
+
+use super::X11Session;
+use crate::backend::{BackendOptions, InputBackend, MouseButton, MouseMotion};
+
+/// X11 backend using the XTEST extension.
+///
+/// XTEST events are indistinguishable from real keyboard input but go to the
+/// focused window, so the target window (found via `_NET_WM_PID`) is activated
+/// first and the previously active window optionally restored afterwards.
+/// Mouse input moves the real pointer, so clicks land on whatever is under it.
+pub struct XTestBackend {
+    session: X11Session,
+    restore_focus: bool,
+}
+
+impl XTestBackend {
+    pub fn new(options: &BackendOptions) -> Result<Self> {
+        let session = X11Session::connect()?;
+
+        if session.conn.extension_information(xtest::X11_EXTENSION_NAME)?.is_none() {
+            anyhow::bail!("The X server does not support the XTEST extension");
+        }
+
+        Ok(Self {
+            session,
+            restore_focus: options.restore_focus,
+        })
+    }
+
+    fn fake_key(&self, key: &str, event_type: u8) -> Result<()> {
+        self.fake_keycode(self.session.keycode(key)?, event_type, key)
+    }
+
+    fn fake_keycode(&self, keycode: Keycode, event_type: u8, what: &str) -> Result<()> {
+        self.session
+            .conn
+            .xtest_fake_input(event_type, keycode, CURRENT_TIME, self.session.root, 0, 0, 0)?
+            .check()
+            .map_err(|e| anyhow::anyhow!("XTest failed for key '{}': {}", what, e))
+    }
+
+    fn fake_button(&self, button: u8, pressed: bool) -> Result<()> {
+        let event_type = if pressed { BUTTON_PRESS_EVENT } else { BUTTON_RELEASE_EVENT };
+        self.session
+            .conn
+            .xtest_fake_input(event_type, button, CURRENT_TIME, self.session.root, 0, 0, 0)?
+            .check()
+            .map_err(|e| anyhow::anyhow!("XTest failed for mouse button {}: {}", button, e))
+    }
+
+    /// Click `button` `count` times, as scroll wheels are buttons 4 to 7 in X11
+    fn fake_clicks(&self, button: u8, count: u32) -> Result<()> {
+        for _ in 0..count {
+            self.fake_button(button, true)?;
+            self.fake_button(button, false)?;
+        }
+        Ok(())
+    }
+}
+
+fn button_number(button: MouseButton) -> u8 {
+    match button {
+        MouseButton::Left => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Right => 3,
+    }
+}
+
+impl InputBackend for XTestBackend {
+    fn name(&self) -> &'static str {
+        "xtest"
+    }
+
+    fn validate_key(&self, key: &str) -> Result<()> {
+        self.session.keycode(key).map(|_| ())
+    }
+
+    fn press(&self, target: u64, key: &str) -> Result<()> {
+        // Leave the target active so the matching release reaches it
+        self.session.with_target_focus(target, false, || self.fake_key(key, KEY_PRESS_EVENT))
+    }
+
+    fn release(&self, target: u64, key: &str) -> Result<()> {
+        self.session.with_target_focus(target, self.restore_focus, || self.fake_key(key, KEY_RELEASE_EVENT))
+    }
+
+    fn tap(&self, target: u64, key: &str, hold: Duration) -> Result<()> {
+        self.session.with_target_focus(target, self.restore_focus, || {
+            self.fake_key(key, KEY_PRESS_EVENT)?;
+            std::thread::sleep(hold);
+            self.fake_key(key, KEY_RELEASE_EVENT)
+        })
+    }
+
+    /// Any character can be typed, those not on the keyboard are mapped on the fly
+    fn type_char(&self, target: u64, c: char, hold: Duration) -> Result<()> {
+        let what = c.to_string();
+        let shift = self.session.keycode("shift")?;
+
+        self.session.with_target_focus(target, self.restore_focus, || {
+            self.session.with_char_keycode(c, |keycode, needs_shift| {
+                if needs_shift {
+                    self.fake_keycode(shift, KEY_PRESS_EVENT, "shift")?;
+                }
+
+                let mut result = self.fake_keycode(keycode, KEY_PRESS_EVENT, &what);
+                if result.is_ok() {
+                    std::thread::sleep(hold);
+                    result = self.fake_keycode(keycode, KEY_RELEASE_EVENT, &what);
+                }
+
+                if needs_shift {
+                    result = result.and(self.fake_keycode(shift, KEY_RELEASE_EVENT, "shift"));
+                }
+                result
+            })
+        })
+    }
+
+    fn validate_unicode(&self, _c: char) -> Result<()> {
+        Ok(())
+    }
+
+    fn validate_mouse(&self, _absolute: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn mouse_move(&self, target: u64, motion: MouseMotion) -> Result<()> {
+        // For motion, detail 0 means absolute root coordinates and 1 relative
+        let (relative, x, y) = match motion {
+            MouseMotion::To { x, y } => (0, x, y),
+            MouseMotion::By { dx, dy } => (1, dx, dy),
+        };
+        let clamp = |v: i32| v.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+
+        self.session.with_target_focus(target, false, || {
+            self.session
+                .conn
+                .xtest_fake_input(MOTION_NOTIFY_EVENT, relative, CURRENT_TIME, self.session.root, clamp(x), clamp(y), 0)?
+                .check()
+                .map_err(|e| anyhow::anyhow!("XTest failed to move the mouse {}: {}", motion, e))
+        })
+    }
+
+    fn mouse_button(&self, target: u64, button: MouseButton, pressed: bool) -> Result<()> {
+        let restore = !pressed && self.restore_focus;
+        self.session.with_target_focus(target, restore, || self.fake_button(button_number(button), pressed))
+    }
+
+    fn click(&self, target: u64, button: MouseButton, hold: Duration) -> Result<()> {
+        self.session.with_target_focus(target, self.restore_focus, || {
+            self.fake_button(button_number(button), true)?;
+            std::thread::sleep(hold);
+            self.fake_button(button_number(button), false)
+        })
+    }
+
+    fn scroll(&self, target: u64, dx: i32, dy: i32) -> Result<()> {
+        self.session.with_target_focus(target, self.restore_focus, || {
+            self.fake_clicks(if dy < 0 { 4 } else { 5 }, dy.unsigned_abs())?;
+            self.fake_clicks(if dx < 0 { 6 } else { 7 }, dx.unsigned_abs())
+        })
+    }
+
+    fn chord(&self, target: u64, keys: &[&str], hold: Duration) -> Result<()> {
+        for key in keys {
+            self.validate_key(key)?;
+        }
+
+        self.session.with_target_focus(target, self.restore_focus, || {
+            let mut pressed = 0;
+            let mut result = Ok(());
+            for key in keys {
+                result = self.fake_key(key, KEY_PRESS_EVENT);
+                if result.is_err() {
+                    break;
+                }
+                pressed += 1;
+            }
+
+            if result.is_ok() {
+                std::thread::sleep(hold);
+            }
+
+            // Release whatever was pressed, even after a failure
+            for key in keys[..pressed].iter().rev() {
+                result = result.and(self.fake_key(key, KEY_RELEASE_EVENT));
+            }
+            result
+        })
+    }
+}
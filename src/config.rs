@@ -1,10 +1,50 @@
 use anyhow::Result;
 use serde::{Deserialize, Deserializer};
+use std::fmt;
 use std::time::Duration;
 
+use crate::automation::{Broadcast, OnTargetExit};
+use crate::backend::{BackendKind, MouseButton, MouseMotion};
+use crate::hotkey::Hotkey;
+use crate::key_sender::KeySender;
+use crate::keys::KeyExpr;
+use crate::layout::Layout;
+use crate::process_finder::{PidSource, ProcessMatcher, SelectionPolicy, TargetMode, TargetSpec};
+use crate::timing::{parse_percent, Delay, Distribution};
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
+    /// Exact name of the target process; shorthand for a `target` with only `name`
+    #[serde(default)]
     pub process_name: String,
+    /// Which process to send keys to, when a name alone is not enough
+    #[serde(default)]
+    pub target: Option<TargetSpec>,
+    /// PID of the target process, skipping the search by name
+    #[serde(default)]
+    pub pid: Option<u32>,
+    /// File holding the target's PID, read again when the process restarts
+    #[serde(default)]
+    pub pidfile: Option<String>,
+    /// Which process to pick when several match
+    #[serde(default)]
+    pub select: SelectionPolicy,
+    /// Send to the selected process or to every matching one
+    #[serde(default)]
+    pub target_mode: TargetMode,
+    /// How keys are scheduled across processes when `target_mode` is `all`
+    #[serde(default)]
+    pub broadcast: Broadcast,
+    /// How often to check that the target still runs, or with `target_mode`
+    /// `all`, to look for new and exited processes
+    #[serde(default = "default_rescan_interval", deserialize_with = "deserialize_delay")]
+    pub rescan_interval: Delay,
+    /// Search for the target again when it exits, or stop
+    #[serde(default)]
+    pub on_target_exit: OnTargetExit,
+    /// Exit code when the target is gone for good
+    #[serde(default = "default_target_exit_code")]
+    pub target_exit_code: u8,
     #[serde(default)]
     pub key_sequence: Vec<KeyAction>,
     #[serde(default)]
@@ -21,57 +61,320 @@ pub struct Config {
     pub repeat_count: u32,
     #[serde(default = "default_restore_focus")]
     pub restore_focus: bool,
+    #[serde(default)]
+    pub backend: BackendKind,
+    /// Where the mock backend writes the key events it receives
+    #[serde(default)]
+    pub mock_output: Option<String>,
+    /// Print keystrokes instead of sending them
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Path of the control socket, `$XDG_RUNTIME_DIR/pks.sock` if unset
+    #[serde(default)]
+    pub control_socket: Option<String>,
+    /// Keyboard layout for backends that send physical keys, detected if unset
+    #[serde(default)]
+    pub layout: Option<Layout>,
+    /// Seed for randomized delays, so a run can be repeated exactly
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// How randomized delays are spread over their range
+    #[serde(default)]
+    pub distribution: Distribution,
+    /// Percentage every fixed delay is randomly shortened or lengthened by
+    #[serde(default, deserialize_with = "deserialize_percent")]
+    pub jitter: u32,
+    /// How long keys stay down during a tap, 30ms if unset
+    #[serde(default, deserialize_with = "deserialize_optional_delay")]
+    pub hold_time: Option<Delay>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawKeyAction")]
 pub struct KeyAction {
-    pub key: String,
-    #[serde(deserialize_with = "deserialize_duration")]
-    pub interval_after: Duration,
+    pub action: Action,
+    /// Pause between the characters of a `text` action
+    pub char_delay: Option<Delay>,
+    pub interval_after: Delay,
+}
+
+/// A `key_sequence` entry as written, before checking which action it names
+#[derive(Deserialize)]
+struct RawKeyAction {
+    key: Option<String>,
+    text: Option<String>,
+    press: Option<String>,
+    release: Option<String>,
+    hold: Option<String>,
+    move_to: Option<[i32; 2]>,
+    move_by: Option<[i32; 2]>,
+    click: Option<MouseButton>,
+    mouse_press: Option<MouseButton>,
+    mouse_release: Option<MouseButton>,
+    scroll: Option<ScrollAmount>,
+    #[serde(default, deserialize_with = "deserialize_optional_delay")]
+    duration: Option<Delay>,
+    #[serde(default, deserialize_with = "deserialize_optional_delay")]
+    char_delay: Option<Delay>,
+    #[serde(deserialize_with = "deserialize_delay")]
+    interval_after: Delay,
+}
+
+impl TryFrom<RawKeyAction> for KeyAction {
+    type Error = String;
+
+    fn try_from(raw: RawKeyAction) -> Result<Self, Self::Error> {
+        let mut actions = Vec::new();
+        if let Some(key) = raw.key {
+            actions.push(Action::Key(key));
+        }
+        if let Some(text) = raw.text {
+            actions.push(Action::Text(text));
+        }
+        if let Some(key) = raw.press {
+            actions.push(Action::Press(key));
+        }
+        if let Some(key) = raw.release {
+            actions.push(Action::Release(key));
+        }
+        if let Some(key) = raw.hold {
+            let duration = raw.duration.ok_or("\"hold\" needs a \"duration\"")?;
+            actions.push(Action::Hold { key, duration });
+        } else if raw.duration.is_some() {
+            return Err("\"duration\" only applies to \"hold\"".to_string());
+        }
+        if let Some([x, y]) = raw.move_to {
+            actions.push(Action::Move(MouseMotion::To { x, y }));
+        }
+        if let Some([dx, dy]) = raw.move_by {
+            actions.push(Action::Move(MouseMotion::By { dx, dy }));
+        }
+        if let Some(button) = raw.click {
+            actions.push(Action::Click(button));
+        }
+        if let Some(button) = raw.mouse_press {
+            actions.push(Action::MousePress(button));
+        }
+        if let Some(button) = raw.mouse_release {
+            actions.push(Action::MouseRelease(button));
+        }
+        if let Some(amount) = raw.scroll {
+            let (dx, dy) = amount.into();
+            actions.push(Action::Scroll { dx, dy });
+        }
+
+        if actions.len() != 1 {
+            return Err(format!(
+                "key_sequence entries need exactly one of {}",
+                Action::KINDS.map(|kind| format!("\"{}\"", kind)).join(", ")
+            ));
+        }
+
+        Ok(Self {
+            action: actions.remove(0),
+            char_delay: raw.char_delay,
+            interval_after: raw.interval_after,
+        })
+    }
+}
+
+/// What one `key_sequence` step does, selected by which field is present
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// `"key"`: a key expression such as `"ctrl+s"`, see [`crate::keys`]
+    Key(String),
+    /// `"text"`: a string typed character by character
+    Text(String),
+    /// `"press"`: hold a key or chord down until a later `release`
+    Press(String),
+    /// `"release"`: let go of keys from an earlier `press`
+    Release(String),
+    /// `"hold"`: press a key or chord, wait `duration`, then release it
+    Hold { key: String, duration: Delay },
+    /// `"move_to": [x, y]` or `"move_by": [dx, dy]`: move the mouse pointer
+    Move(MouseMotion),
+    /// `"click"`: press and release a mouse button
+    Click(MouseButton),
+    /// `"mouse_press"`: hold a mouse button down until a later `mouse_release`
+    MousePress(MouseButton),
+    /// `"mouse_release"`: let go of a button from an earlier `mouse_press`
+    MouseRelease(MouseButton),
+    /// `"scroll"`: turn the wheel by whole notches, positive `dy` is down
+    Scroll { dx: i32, dy: i32 },
+}
+
+impl Action {
+    pub const KINDS: [&'static str; 11] = [
+        "key", "text", "press", "release", "hold",
+        "move_to", "move_by", "click", "mouse_press", "mouse_release", "scroll",
+    ];
+
+    /// The key expression this action sends, if it sends keys
+    pub fn key_expr(&self) -> Option<&str> {
+        match self {
+            Action::Key(key) | Action::Press(key) | Action::Release(key) | Action::Hold { key, .. } => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Whether this action drives the mouse rather than the keyboard
+    pub fn is_mouse(&self) -> bool {
+        matches!(
+            self,
+            Action::Move(_) | Action::Click(_) | Action::MousePress(_) | Action::MouseRelease(_) | Action::Scroll { .. }
+        )
+    }
+}
+
+/// `"scroll": 3` scrolls down three notches, `"scroll": [dx, dy]` scrolls on both axes
+#[derive(Debug, Clone, Copy, Deserialize, serde::Serialize)]
+#[serde(untagged)]
+enum ScrollAmount {
+    Vertical(i32),
+    Both([i32; 2]),
+}
+
+impl From<ScrollAmount> for (i32, i32) {
+    fn from(amount: ScrollAmount) -> Self {
+        match amount {
+            ScrollAmount::Vertical(dy) => (0, dy),
+            ScrollAmount::Both([dx, dy]) => (dx, dy),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Key(key) => write!(f, "{}", key),
+            Action::Text(text) => write!(f, "text {:?}", text),
+            Action::Press(key) => write!(f, "press {}", key),
+            Action::Release(key) => write!(f, "release {}", key),
+            Action::Hold { key, duration } => write!(f, "hold {} for {}", key, duration),
+            Action::Move(motion) => write!(f, "move mouse {}", motion),
+            Action::Click(button) => write!(f, "click {}", button),
+            Action::MousePress(button) => write!(f, "press mouse {}", button),
+            Action::MouseRelease(button) => write!(f, "release mouse {}", button),
+            Action::Scroll { dx, dy } => write!(f, "scroll {},{}", dx, dy),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct IndependentKey {
     pub key: String,
-    #[serde(deserialize_with = "deserialize_duration")]
-    pub interval: Duration,
+    #[serde(deserialize_with = "deserialize_delay")]
+    pub interval: Delay,
+}
+
+// Custom deserializer for delays like "1000ms", "5s", "900ms..1200ms" or "1s±10%"
+fn deserialize_delay<'de, D>(deserializer: D) -> Result<Delay, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    Delay::parse(&s).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_optional_delay<'de, D>(deserializer: D) -> Result<Option<Delay>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| Delay::parse(&s).map_err(serde::de::Error::custom))
+        .transpose()
 }
 
-// Custom deserializer for duration strings like "1000ms", "5s", "1m"
-fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+// Percentages are written "10%"
+fn deserialize_percent<'de, D>(deserializer: D) -> Result<u32, D::Error>
 where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    parse_duration(&s).map_err(serde::de::Error::custom)
+    parse_percent(&s).map_err(serde::de::Error::custom)
 }
 
-// Parse duration from string
+// Nanoseconds per duration unit, largest first
+const DURATION_UNITS: &[(&str, u128)] = &[
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("µs", 1_000),
+    ("ns", 1),
+];
+
+// Parse duration from string: "1000ms", "1.5s", "2h", "1m30s", "250us".
+// A bare number is milliseconds.
 pub fn parse_duration(s: &str) -> Result<Duration> {
     let s = s.trim().to_lowercase();
+    if s.is_empty() {
+        anyhow::bail!("Empty duration");
+    }
 
-    // Handle different formats
-    if s.ends_with("ms") {
-        let num_str = &s[..s.len() - 2];
-        let ms: u64 = num_str.parse()
-            .map_err(|_| anyhow::anyhow!("Invalid milliseconds value: {}", num_str))?;
-        Ok(Duration::from_millis(ms))
-    } else if s.ends_with('s') {
-        let num_str = &s[..s.len() - 1];
-        let secs: u64 = num_str.parse()
-            .map_err(|_| anyhow::anyhow!("Invalid seconds value: {}", num_str))?;
-        Ok(Duration::from_secs(secs))
-    } else if s.ends_with('m') {
-        let num_str = &s[..s.len() - 1];
-        let mins: u64 = num_str.parse()
-            .map_err(|_| anyhow::anyhow!("Invalid minutes value: {}", num_str))?;
-        Ok(Duration::from_secs(mins * 60))
-    } else {
-        // Default to milliseconds if no suffix
-        let ms: u64 = s.parse()
-            .map_err(|_| anyhow::anyhow!("Invalid duration value: {}", s))?;
-        Ok(Duration::from_millis(ms))
+    // Default to milliseconds if no suffix
+    if s.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        return nanos_to_duration(parse_scaled(&s, 1_000_000, &s)?, &s);
     }
+
+    let mut total: u128 = 0;
+    let mut last_unit = None;
+    let mut rest = s.as_str();
+    while !rest.is_empty() {
+        let num_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let (num_str, after) = rest.split_at(num_len);
+        if num_str.is_empty() {
+            anyhow::bail!("Invalid duration value: {}", s);
+        }
+        let unit_len = after.find(|c: char| c.is_ascii_digit() || c == '.' || c.is_whitespace()).unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_len);
+        let index = DURATION_UNITS.iter().position(|(name, _)| *name == unit)
+            .ok_or_else(|| match unit {
+                "" => anyhow::anyhow!("Missing unit after '{}' in duration: {}", num_str, s),
+                _ => anyhow::anyhow!("Unknown duration unit '{}' in: {} (use h, m, s, ms, us or ns)", unit, s),
+            })?;
+        if last_unit.is_some_and(|last| index <= last) {
+            anyhow::bail!("Duration units must go from largest to smallest without repeating: {}", s);
+        }
+        last_unit = Some(index);
+
+        total = total.checked_add(parse_scaled(num_str, DURATION_UNITS[index].1, &s)?)
+            .ok_or_else(|| anyhow::anyhow!("Duration too large: {}", s))?;
+        rest = after.trim_start();
+    }
+
+    nanos_to_duration(total, &s)
+}
+
+// Parse a decimal number and scale it to nanoseconds without going through floats
+fn parse_scaled(num_str: &str, unit_nanos: u128, input: &str) -> Result<u128> {
+    let (whole, frac) = num_str.split_once('.').unwrap_or((num_str, ""));
+    if whole.is_empty() && frac.is_empty() || frac.contains('.') {
+        anyhow::bail!("Invalid duration value: {}", input);
+    }
+
+    let invalid = |_| anyhow::anyhow!("Invalid duration value: {}", input);
+    let too_large = || anyhow::anyhow!("Duration too large: {}", input);
+    let whole: u128 = if whole.is_empty() { 0 } else { whole.parse().map_err(invalid)? };
+    let mut nanos = whole.checked_mul(unit_nanos).ok_or_else(too_large)?;
+
+    let frac = frac.trim_end_matches('0');
+    if !frac.is_empty() {
+        let scale = u32::try_from(frac.len()).ok()
+            .and_then(|len| 10u128.checked_pow(len))
+            .filter(|scale| unit_nanos % scale == 0)
+            .ok_or_else(|| anyhow::anyhow!("Duration is more precise than a nanosecond: {}", input))?;
+        let frac: u128 = frac.parse().map_err(invalid)?;
+        nanos += frac * (unit_nanos / scale);
+    }
+    Ok(nanos)
+}
+
+fn nanos_to_duration(nanos: u128, input: &str) -> Result<Duration> {
+    let secs = u64::try_from(nanos / 1_000_000_000)
+        .map_err(|_| anyhow::anyhow!("Duration too large: {}", input))?;
+    Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32))
 }
 
 // Default values
@@ -91,7 +394,13 @@ fn default_restore_focus() -> bool {
     true
 }
 
-// ... rest of the config.rs implementation stays the same ...
+fn default_rescan_interval() -> Delay {
+    Delay::fixed(Duration::from_secs(2))
+}
+
+fn default_target_exit_code() -> u8 {
+    3
+}
 
 impl Config {
     /// Load configuration from a JSON file
@@ -120,52 +429,330 @@ impl Config {
         Ok(())
     }
 
-    /// Validate the configuration
+    /// The process to send keys to; a plain `process_name` matches that exact name
+    pub fn target(&self) -> TargetSpec {
+        self.target.clone().unwrap_or_else(|| TargetSpec::name(&self.process_name))
+    }
+
+    /// The `pid` or `pidfile` naming the target, if the config has one
+    pub fn pid_source(&self) -> Option<PidSource> {
+        match (self.pid, &self.pidfile) {
+            (Some(pid), _) => Some(PidSource::Pid(pid)),
+            (None, Some(pidfile)) => Some(PidSource::Pidfile(pidfile.into())),
+            (None, None) => None,
+        }
+    }
+
+    /// Validate the configuration, failing with every error found
     pub fn validate(&self) -> Result<()> {
-        if self.process_name.trim().is_empty() {
-            anyhow::bail!("process_name cannot be empty");
+        self.check().into_result()
+    }
+
+    /// Check the configuration on its own, without a backend
+    pub fn check(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        let given: Vec<&str> = [
+            ("process_name", !self.process_name.is_empty()),
+            ("target", self.target.is_some()),
+            ("pid", self.pid.is_some()),
+            ("pidfile", self.pidfile.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(field, set)| set.then_some(field))
+        .collect();
+
+        match given.as_slice() {
+            [] | ["process_name"] if self.process_name.trim().is_empty() => report.error("process_name", "cannot be empty"),
+            [_] | [] => {}
+            [first, second] => report.error("", format!("Cannot specify both {} and {}. Choose one.", first, second)),
+            _ => report.error("", format!("Cannot specify more than one of {}. Choose one.", given.join(", "))),
+        }
+
+        if let Some(target) = &self.target {
+            if let Err(e) = ProcessMatcher::new(target) {
+                report.error("target", e);
+            }
+        }
+        if self.pid == Some(0) {
+            report.error("pid", "must be greater than 0");
+        }
+        if self.pidfile.as_ref().is_some_and(|pidfile| pidfile.trim().is_empty()) {
+            report.error("pidfile", "cannot be empty");
+        }
+        if self.target_mode == TargetMode::All && self.pid_source().is_some() {
+            report.error("target_mode", "all needs process_name or target to search for, not a single pid");
         }
 
         if self.key_sequence.is_empty() && self.independent_keys.is_empty() {
-            anyhow::bail!("At least one key_sequence or independent_keys entry is required");
+            report.error("", "At least one key_sequence or independent_keys entry is required");
         }
 
         if !self.key_sequence.is_empty() && !self.independent_keys.is_empty() {
-            anyhow::bail!("Cannot specify both key_sequence and independent_keys. Choose one mode.");
+            report.error("", "Cannot specify both key_sequence and independent_keys. Choose one mode.");
         }
 
         if self.max_retries == 0 {
-            anyhow::bail!("max_retries must be greater than 0");
+            report.error("max_retries", "must be greater than 0");
+        }
+
+        if let Err(e) = Hotkey::parse(&self.pause_hotkey) {
+            report.error("pause_hotkey", e);
+        }
+
+        // Every rescan lists all processes on the system
+        if self.rescan_interval.min < Duration::from_millis(100) {
+            report.error("rescan_interval", "must be at least 100ms");
         }
 
         // Validate key sequences
         for (i, key_action) in self.key_sequence.iter().enumerate() {
-            if key_action.key.trim().is_empty() {
-                anyhow::bail!("key_sequence[{}]: key cannot be empty", i);
+            let path = format!("key_sequence[{}]", i);
+
+            match &key_action.action {
+                Action::Text(text) => {
+                    if text.is_empty() {
+                        report.error(&path, "text cannot be empty");
+                    }
+                }
+                Action::Key(key) => {
+                    if let Err(e) = KeyExpr::parse(key) {
+                        report.error(&path, e);
+                    }
+                }
+                Action::Press(key) | Action::Release(key) | Action::Hold { key, .. } => match KeyExpr::parse(key) {
+                    Ok(KeyExpr::Key(_) | KeyExpr::Chord(_)) => {}
+                    Ok(_) => report.error(&path, format!("{} takes a single key or chord, not '{}'", key_action.action, key)),
+                    Err(e) => report.error(&path, e),
+                },
+                Action::Scroll { dx: 0, dy: 0 } => {
+                    report.error(&path, "scroll amount cannot be zero");
+                }
+                Action::Move(_) | Action::Click(_) | Action::MousePress(_) | Action::MouseRelease(_) | Action::Scroll { .. } => {}
             }
-            if key_action.interval_after < Duration::from_millis(1) {
-                anyhow::bail!("key_sequence[{}]: interval_after must be at least 1ms", i);
+
+            if key_action.char_delay.is_some() && !matches!(key_action.action, Action::Text(_)) {
+                report.error(&path, "char_delay only applies to text actions");
             }
+            check_interval(&mut report, &path, "interval_after", key_action.interval_after);
         }
 
         // Validate independent keys
         for (i, independent_key) in self.independent_keys.iter().enumerate() {
-            if independent_key.key.trim().is_empty() {
-                anyhow::bail!("independent_keys[{}]: key cannot be empty", i);
+            let path = format!("independent_keys[{}]", i);
+            if let Err(e) = KeyExpr::parse(&independent_key.key) {
+                report.error(&path, e);
+            }
+            check_interval(&mut report, &path, "interval", independent_key.interval);
+        }
+
+        report
+    }
+
+    /// Check the configuration and that `key_sender`'s backend can send
+    /// every key, text and mouse action in it
+    pub fn check_with(&self, key_sender: &KeySender) -> ValidationReport {
+        let mut report = self.check();
+
+        for (i, key_action) in self.key_sequence.iter().enumerate() {
+            let path = format!("key_sequence[{}]", i);
+            // Keys that don't parse have been reported already
+            if report.has_error_at(&path) {
+                continue;
+            }
+
+            let result = match &key_action.action {
+                Action::Text(text) => key_sender.validate_text(text),
+                Action::Move(motion) => key_sender.validate_mouse(motion.is_absolute()),
+                action if action.is_mouse() => key_sender.validate_mouse(false),
+                action => action.key_expr().map_or(Ok(()), |key| key_sender.parse_key_for_validation(key)),
+            };
+            if let Err(e) = result {
+                report.error(&path, format!("{}: {}", key_action.action, e));
+            }
+        }
+
+        for (i, independent_key) in self.independent_keys.iter().enumerate() {
+            let path = format!("independent_keys[{}]", i);
+            if report.has_error_at(&path) {
+                continue;
             }
-            if independent_key.interval < Duration::from_millis(1) {
-                anyhow::bail!("independent_keys[{}]: interval must be at least 1ms", i);
+            if let Err(e) = key_sender.parse_key_for_validation(&independent_key.key) {
+                report.error(&path, format!("{}: {}", independent_key.key, e));
             }
         }
 
-        Ok(())
+        report
+    }
+
+    /// Check that `new` only changes settings a running pks picks up on reload.
+    ///
+    /// The target, the key sender and the monitoring around them keep the
+    /// values they started with, so a reload that changes them is rejected
+    /// instead of silently ignored.
+    pub fn check_reload(&self, new: &Config) -> Result<()> {
+        let mut report = ValidationReport::default();
+        let fixed = [
+            ("process_name", self.process_name != new.process_name),
+            ("target", self.target != new.target),
+            ("pid", self.pid != new.pid),
+            ("pidfile", self.pidfile != new.pidfile),
+            ("select", self.select != new.select),
+            ("target_mode", self.target_mode != new.target_mode),
+            ("rescan_interval", self.rescan_interval != new.rescan_interval),
+            ("on_target_exit", self.on_target_exit != new.on_target_exit),
+            ("target_exit_code", self.target_exit_code != new.target_exit_code),
+            // The key sender, hotkey and control socket are set up once at startup
+            ("backend", self.backend != new.backend),
+            ("layout", self.layout != new.layout),
+            ("dry_run", self.dry_run != new.dry_run),
+            ("restore_focus", self.restore_focus != new.restore_focus),
+            ("mock_output", self.mock_output != new.mock_output),
+            ("pause_hotkey", self.pause_hotkey != new.pause_hotkey),
+            ("control_socket", self.control_socket != new.control_socket),
+        ];
+        for (field, changed) in fixed {
+            if changed {
+                report.error(field, "cannot be changed by a reload, restart pks to apply it");
+            }
+        }
+        report.into_result()
+    }
+}
+
+/// Intervals must be at least 1ms and should be at least [`MIN_RECOMMENDED_INTERVAL`]
+fn check_interval(report: &mut ValidationReport, path: &str, field: &str, interval: Delay) {
+    if interval.min < Duration::from_millis(1) {
+        report.error(path, format!("{} must be at least 1ms", field));
+    } else if interval.min < MIN_RECOMMENDED_INTERVAL {
+        report.warning(path, format!("very short {} ({}) may cause issues", field, interval));
+    }
+}
+
+/// Intervals shorter than this are accepted with a warning
+pub const MIN_RECOMMENDED_INTERVAL: Duration = Duration::from_millis(50);
+
+/// One problem found in a [`Config`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Where the problem is, e.g. `"key_sequence[2]"`, or empty for the whole config
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Result of [`Config::check`]: errors stop a config from running, warnings don't
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// `Err` describing every error, one per line when there are several
+    pub fn into_result(self) -> Result<()> {
+        match &self.errors[..] {
+            [] => Ok(()),
+            [error] => anyhow::bail!("{}", error),
+            errors => {
+                let lines: Vec<String> = errors.iter().map(|error| format!("  {}", error)).collect();
+                anyhow::bail!("{} configuration errors:\n{}", errors.len(), lines.join("\n"))
+            }
+        }
+    }
+
+    fn error(&mut self, path: &str, message: impl fmt::Display) {
+        self.errors.push(ValidationIssue { path: path.to_string(), message: message.to_string() });
+    }
+
+    fn warning(&mut self, path: &str, message: impl fmt::Display) {
+        self.warnings.push(ValidationIssue { path: path.to_string(), message: message.to_string() });
+    }
+
+    fn has_error_at(&self, path: &str) -> bool {
+        self.errors.iter().any(|error| error.path == path)
+    }
+}
+
+/// Settings given on the command line. They win over the config file, also
+/// each time it is reloaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliOverrides {
+    pub pid: Option<u32>,
+    pub pidfile: Option<String>,
+    pub backend: Option<BackendKind>,
+    pub layout: Option<Layout>,
+    pub select: Option<SelectionPolicy>,
+    pub seed: Option<u64>,
+    pub all: bool,
+    pub dry_run: bool,
+    pub control_socket: Option<String>,
+}
+
+impl CliOverrides {
+    pub fn apply(&self, config: &mut Config) {
+        // --pid and --pidfile name the target directly, replacing the config's
+        if self.pid.is_some() || self.pidfile.is_some() {
+            config.process_name.clear();
+            config.target = None;
+            config.pid = self.pid;
+            config.pidfile = self.pidfile.clone();
+        }
+
+        if let Some(backend) = self.backend {
+            config.backend = backend;
+        }
+        if let Some(layout) = self.layout {
+            config.layout = Some(layout);
+        }
+        if let Some(select) = self.select {
+            config.select = select;
+        }
+        if let Some(seed) = self.seed {
+            config.seed = Some(seed);
+        }
+        if self.all {
+            config.target_mode = TargetMode::All;
+        }
+        if self.dry_run {
+            config.dry_run = true;
+        }
+        if let Some(socket) = &self.control_socket {
+            config.control_socket = Some(socket.clone());
+        }
     }
 }
 
 // Helper struct for saving config with string durations
 #[derive(serde::Serialize)]
 struct ConfigForSave {
+    #[serde(skip_serializing_if = "String::is_empty")]
     process_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<TargetSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pidfile: Option<String>,
+    select: SelectionPolicy,
+    target_mode: TargetMode,
+    broadcast: Broadcast,
+    rescan_interval: String,
+    on_target_exit: OnTargetExit,
+    target_exit_code: u8,
     key_sequence: Vec<KeyActionForSave>,
     independent_keys: Vec<IndependentKeyForSave>,
     max_retries: u32,
@@ -174,14 +761,84 @@ struct ConfigForSave {
     loop_sequence: bool,
     repeat_count: u32,
     restore_focus: bool,
+    backend: BackendKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mock_output: Option<String>,
+    dry_run: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    control_socket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    layout: Option<Layout>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    distribution: Distribution,
+    jitter: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hold_time: Option<String>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Default)]
 struct KeyActionForSave {
-    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    press: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hold: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    move_to: Option<[i32; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    move_by: Option<[i32; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    click: Option<MouseButton>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mouse_press: Option<MouseButton>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mouse_release: Option<MouseButton>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scroll: Option<ScrollAmount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    char_delay: Option<String>,
     interval_after: String,
 }
 
+impl From<KeyAction> for KeyActionForSave {
+    fn from(key_action: KeyAction) -> Self {
+        let saved = KeyActionForSave {
+            char_delay: key_action.char_delay.map(|delay| delay.to_string()),
+            interval_after: key_action.interval_after.to_string(),
+            ..Default::default()
+        };
+
+        match key_action.action {
+            Action::Key(key) => KeyActionForSave { key: Some(key), ..saved },
+            Action::Text(text) => KeyActionForSave { text: Some(text), ..saved },
+            Action::Press(key) => KeyActionForSave { press: Some(key), ..saved },
+            Action::Release(key) => KeyActionForSave { release: Some(key), ..saved },
+            Action::Hold { key, duration } => KeyActionForSave {
+                hold: Some(key),
+                duration: Some(duration.to_string()),
+                ..saved
+            },
+            Action::Move(MouseMotion::To { x, y }) => KeyActionForSave { move_to: Some([x, y]), ..saved },
+            Action::Move(MouseMotion::By { dx, dy }) => KeyActionForSave { move_by: Some([dx, dy]), ..saved },
+            Action::Click(button) => KeyActionForSave { click: Some(button), ..saved },
+            Action::MousePress(button) => KeyActionForSave { mouse_press: Some(button), ..saved },
+            Action::MouseRelease(button) => KeyActionForSave { mouse_release: Some(button), ..saved },
+            Action::Scroll { dx, dy } => {
+                let amount = if dx == 0 { ScrollAmount::Vertical(dy) } else { ScrollAmount::Both([dx, dy]) };
+                KeyActionForSave { scroll: Some(amount), ..saved }
+            }
+        }
+    }
+}
+
 #[derive(serde::Serialize)]
 struct IndependentKeyForSave {
     key: String,
@@ -192,13 +849,19 @@ impl From<Config> for ConfigForSave {
     fn from(config: Config) -> Self {
         ConfigForSave {
             process_name: config.process_name,
-            key_sequence: config.key_sequence.into_iter().map(|ka| KeyActionForSave {
-                key: ka.key,
-                interval_after: duration_to_string(ka.interval_after),
-            }).collect(),
+            target: config.target,
+            pid: config.pid,
+            pidfile: config.pidfile,
+            select: config.select,
+            target_mode: config.target_mode,
+            broadcast: config.broadcast,
+            rescan_interval: config.rescan_interval.to_string(),
+            on_target_exit: config.on_target_exit,
+            target_exit_code: config.target_exit_code,
+            key_sequence: config.key_sequence.into_iter().map(KeyActionForSave::from).collect(),
             independent_keys: config.independent_keys.into_iter().map(|ik| IndependentKeyForSave {
                 key: ik.key,
-                interval: duration_to_string(ik.interval),
+                interval: ik.interval.to_string(),
             }).collect(),
             max_retries: config.max_retries,
             pause_hotkey: config.pause_hotkey,
@@ -206,20 +869,52 @@ impl From<Config> for ConfigForSave {
             loop_sequence: config.loop_sequence,
             repeat_count: config.repeat_count,
             restore_focus: config.restore_focus,
+            backend: config.backend,
+            mock_output: config.mock_output,
+            dry_run: config.dry_run,
+            control_socket: config.control_socket,
+            layout: config.layout,
+            seed: config.seed,
+            distribution: config.distribution,
+            jitter: format!("{}%", config.jitter),
+            hold_time: config.hold_time.map(|delay| delay.to_string()),
         }
     }
 }
 
-fn duration_to_string(duration: Duration) -> String {
-    let ms = duration.as_millis();
+/// Format a duration so that `parse_duration` reads it back unchanged: whole
+/// hours, minutes and seconds are combined ("1h30m", "1m30s"), a fractional
+/// part is written as decimal seconds ("1.5s"), and anything under a second
+/// uses the largest exact unit ("500ms", "250us").
+pub fn duration_to_string(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let nanos = duration.subsec_nanos();
 
-    if ms >= 60000 && ms % 60000 == 0 {
-        format!("{}m", ms / 60000)
-    } else if ms >= 1000 && ms % 1000 == 0 {
-        format!("{}s", ms / 1000)
-    } else {
-        format!("{}ms", ms)
+    if secs == 0 {
+        return if nanos % 1_000_000 == 0 {
+            format!("{}ms", nanos / 1_000_000)
+        } else if nanos % 1_000 == 0 {
+            format!("{}us", nanos / 1_000)
+        } else {
+            format!("{}ns", nanos)
+        };
     }
+
+    let mut out = String::new();
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        out.push_str(&format!("{}h", hours));
+    }
+    if mins > 0 {
+        out.push_str(&format!("{}m", mins));
+    }
+    if nanos > 0 {
+        let frac = format!("{:09}", nanos);
+        out.push_str(&format!("{}.{}s", secs, frac.trim_end_matches('0')));
+    } else if secs > 0 {
+        out.push_str(&format!("{}s", secs));
+    }
+    out
 }
 
 #[cfg(test)]
@@ -245,9 +940,69 @@ mod tests {
     #[test]
     fn test_duration_to_string() {
         assert_eq!(duration_to_string(Duration::from_millis(1000)), "1s");
-        assert_eq!(duration_to_string(Duration::from_millis(1500)), "1500ms");
+        assert_eq!(duration_to_string(Duration::from_millis(1500)), "1.5s");
         assert_eq!(duration_to_string(Duration::from_millis(60000)), "1m");
         assert_eq!(duration_to_string(Duration::from_millis(500)), "500ms");
+        assert_eq!(duration_to_string(Duration::from_secs(90)), "1m30s");
+        assert_eq!(duration_to_string(Duration::from_secs(7200)), "2h");
+        assert_eq!(duration_to_string(Duration::from_secs(3660)), "1h1m");
+        assert_eq!(duration_to_string(Duration::from_millis(60500)), "1m0.5s");
+        assert_eq!(duration_to_string(Duration::from_micros(250)), "250us");
+        assert_eq!(duration_to_string(Duration::from_nanos(1500)), "1500ns");
+        assert_eq!(duration_to_string(Duration::ZERO), "0ms");
+    }
+
+    #[test]
+    fn test_richer_duration_syntax() {
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_duration("1m30s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1h 2m 3.25s").unwrap(), Duration::from_millis(3_723_250));
+        assert_eq!(parse_duration("250us").unwrap(), Duration::from_micros(250));
+        assert_eq!(parse_duration("250µs").unwrap(), Duration::from_micros(250));
+        assert_eq!(parse_duration("0.5ms").unwrap(), Duration::from_micros(500));
+        assert_eq!(parse_duration(".25s").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("1.5").unwrap(), Duration::from_micros(1500));
+        assert_eq!(parse_duration("1s500ms").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("1.000000000000s").unwrap(), Duration::from_secs(1));
+
+        assert!(parse_duration("30s1m").is_err());
+        assert!(parse_duration("1s1s").is_err());
+        assert!(parse_duration("1.5ns").is_err());
+        assert!(parse_duration("1..5s").is_err());
+        assert!(parse_duration(".s").is_err());
+        assert!(parse_duration("1m30").is_err());
+        assert!(parse_duration("5d").is_err());
+        assert!(parse_duration("99999999999999999999h").is_err());
+    }
+
+    #[test]
+    fn test_duration_round_trip() {
+        let samples = [
+            Duration::ZERO,
+            Duration::from_nanos(1),
+            Duration::from_nanos(999_999_999),
+            Duration::from_micros(250),
+            Duration::from_millis(1),
+            Duration::from_millis(1500),
+            Duration::from_millis(59_999),
+            Duration::from_secs(60),
+            Duration::from_millis(90_250),
+            Duration::from_secs(3600),
+            Duration::new(86_399, 1),
+            Duration::new(u64::MAX, 999_999_999),
+        ];
+        for duration in samples {
+            let text = duration_to_string(duration);
+            assert_eq!(parse_duration(&text).unwrap(), duration, "{}", text);
+            // The canonical form is stable
+            assert_eq!(duration_to_string(parse_duration(&text).unwrap()), text);
+        }
+
+        for text in ["1000ms", "1.5s", "2h", "1m30s", "250us", "1h0.001s", "42ns"] {
+            let duration = parse_duration(text).unwrap();
+            assert_eq!(parse_duration(&duration_to_string(duration)).unwrap(), duration, "{}", text);
+        }
     }
 
     #[test]
@@ -276,16 +1031,293 @@ mod tests {
         assert_eq!(config.independent_keys[1].key, "a");
         assert_eq!(config.independent_keys[1].interval, Duration::from_secs(5));
         assert!(config.restore_focus); // Test default value
+        assert_eq!(config.backend, BackendKind::Auto);
+    }
+
+    #[test]
+    fn test_text_action_parsing() {
+        let json = r#"
+        {
+            "process_name": "chat.exe",
+            "key_sequence": [
+                {"text": "Hello, World!", "char_delay": "20ms", "interval_after": "1s"},
+                {"key": "enter", "interval_after": "500ms"}
+            ]
+        }
+        "#;
+
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.key_sequence[0].action, Action::Text("Hello, World!".to_string()));
+        assert_eq!(config.key_sequence[0].char_delay, Some(Duration::from_millis(20).into()));
+        assert_eq!(config.key_sequence[1].action, Action::Key("enter".to_string()));
+        assert_eq!(config.key_sequence[1].char_delay, None);
+        assert!(config.validate().is_ok());
+
+        let json = r#"{"process_name": "a", "key_sequence": [{"key": "a", "char_delay": "5ms", "interval_after": "1s"}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert!(config.validate().is_err());
+
+        let json = r#"{"process_name": "a", "key_sequence": [{"interval_after": "1s"}]}"#;
+        assert!(serde_json::from_str::<Config>(json).is_err());
+
+        let json = r#"{"process_name": "a", "key_sequence": [{"key": "a", "text": "b", "interval_after": "1s"}]}"#;
+        assert!(serde_json::from_str::<Config>(json).is_err());
+    }
+
+    #[test]
+    fn test_press_release_hold_parsing() {
+        let json = r#"
+        {
+            "process_name": "game.exe",
+            "key_sequence": [
+                {"press": "shift", "interval_after": "10ms"},
+                {"hold": "w", "duration": "2s", "interval_after": "10ms"},
+                {"release": "shift", "interval_after": "10ms"}
+            ]
+        }
+        "#;
+
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.key_sequence[0].action, Action::Press("shift".to_string()));
+        assert_eq!(
+            config.key_sequence[1].action,
+            Action::Hold { key: "w".to_string(), duration: Duration::from_secs(2).into() }
+        );
+        assert_eq!(config.key_sequence[2].action, Action::Release("shift".to_string()));
+        assert!(config.validate().is_ok());
+
+        // hold needs a duration, and nothing else takes one
+        let json = r#"{"process_name": "a", "key_sequence": [{"hold": "w", "interval_after": "1s"}]}"#;
+        assert!(serde_json::from_str::<Config>(json).is_err());
+        let json = r#"{"process_name": "a", "key_sequence": [{"key": "w", "duration": "1s", "interval_after": "1s"}]}"#;
+        assert!(serde_json::from_str::<Config>(json).is_err());
+
+        // Sequences can't be held
+        let json = r#"{"process_name": "a", "key_sequence": [{"press": "a b", "interval_after": "1s"}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_mouse_action_parsing() {
+        let json = r#"
+        {
+            "process_name": "game.exe",
+            "key_sequence": [
+                {"move_to": [640, 360], "interval_after": "10ms"},
+                {"move_by": [-5, 12], "interval_after": "10ms"},
+                {"click": "left", "interval_after": "10ms"},
+                {"mouse_press": "right", "interval_after": "10ms"},
+                {"mouse_release": "right", "interval_after": "10ms"},
+                {"scroll": 3, "interval_after": "10ms"},
+                {"scroll": [-1, 0], "interval_after": "10ms"}
+            ]
+        }
+        "#;
+
+        let config: Config = serde_json::from_str(json).unwrap();
+        let actions: Vec<Action> = config.key_sequence.iter().map(|a| a.action.clone()).collect();
+        assert_eq!(actions, vec![
+            Action::Move(MouseMotion::To { x: 640, y: 360 }),
+            Action::Move(MouseMotion::By { dx: -5, dy: 12 }),
+            Action::Click(MouseButton::Left),
+            Action::MousePress(MouseButton::Right),
+            Action::MouseRelease(MouseButton::Right),
+            Action::Scroll { dx: 0, dy: 3 },
+            Action::Scroll { dx: -1, dy: 0 },
+        ]);
+        assert!(config.validate().is_ok());
+        assert_eq!(actions[1].to_string(), "move mouse by -5,12");
+        assert_eq!(actions[2].to_string(), "click left");
+
+        // Unknown buttons, and mixing mouse and key fields, are rejected
+        let json = r#"{"process_name": "a", "key_sequence": [{"click": "fourth", "interval_after": "1s"}]}"#;
+        assert!(serde_json::from_str::<Config>(json).is_err());
+        let json = r#"{"process_name": "a", "key_sequence": [{"click": "left", "key": "a", "interval_after": "1s"}]}"#;
+        assert!(serde_json::from_str::<Config>(json).is_err());
+
+        let json = r#"{"process_name": "a", "key_sequence": [{"scroll": [0, 0], "interval_after": "1s"}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_target_config() {
+        let json = r#"{"target": {"name": "java", "cmdline": "minecraft"}, "independent_keys": [{"key": "a", "interval": "1s"}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.target().cmdline.as_deref(), Some("minecraft"));
+
+        // A plain process_name is an exact name
+        let json = r#"{"process_name": "java", "independent_keys": [{"key": "a", "interval": "1s"}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.target(), TargetSpec::name("java"));
+
+        let errors = |json: &str| {
+            let config: Config = serde_json::from_str(json).unwrap();
+            config.check().errors.iter().map(ToString::to_string).collect::<Vec<_>>()
+        };
+        let both = r#"{"process_name": "java", "target": {"name": "java"}, "independent_keys": [{"key": "a", "interval": "1s"}]}"#;
+        assert_eq!(errors(both), ["Cannot specify both process_name and target. Choose one."]);
+        let bad_regex = r#"{"target": {"cmdline_regex": "(x"}, "independent_keys": [{"key": "a", "interval": "1s"}]}"#;
+        assert!(errors(bad_regex)[0].starts_with("target: Invalid cmdline_regex '(x'"));
+        let empty = r#"{"target": {}, "independent_keys": [{"key": "a", "interval": "1s"}]}"#;
+        assert!(errors(empty)[0].starts_with("target: target needs at least one of"));
+
+        let pid = r#"{"pid": 4242, "independent_keys": [{"key": "a", "interval": "1s"}]}"#;
+        assert!(errors(pid).is_empty());
+        let config: Config = serde_json::from_str(pid).unwrap();
+        assert_eq!(config.pid_source(), Some(PidSource::Pid(4242)));
+        let pidfile = r#"{"pidfile": "/run/game.pid", "independent_keys": [{"key": "a", "interval": "1s"}]}"#;
+        let config: Config = serde_json::from_str(pidfile).unwrap();
+        assert_eq!(config.pid_source(), Some(PidSource::Pidfile("/run/game.pid".into())));
+
+        let name_and_pid = r#"{"process_name": "java", "pid": 1, "pidfile": "x", "independent_keys": [{"key": "a", "interval": "1s"}]}"#;
+        assert_eq!(errors(name_and_pid), ["Cannot specify more than one of process_name, pid, pidfile. Choose one."]);
+        let all_by_pid = r#"{"pid": 0, "target_mode": "all", "independent_keys": [{"key": "a", "interval": "1s"}]}"#;
+        assert_eq!(errors(all_by_pid), [
+            "pid: must be greater than 0",
+            "target_mode: all needs process_name or target to search for, not a single pid",
+        ]);
+    }
+
+    #[test]
+    fn test_pause_hotkey_validation() {
+        let errors = |hotkey: &str| {
+            let json = format!(r#"{{"process_name": "java", "pause_hotkey": "{}", "independent_keys": [{{"key": "a", "interval": "1s"}}]}}"#, hotkey);
+            let config: Config = serde_json::from_str(&json).unwrap();
+            config.check().errors.iter().map(ToString::to_string).collect::<Vec<_>>()
+        };
+
+        assert!(errors("ctrl+alt+p").is_empty());
+        assert!(errors("lsuper+f12").is_empty());
+        assert_eq!(errors("a+b"), ["pause_hotkey: 'a' is not a modifier in hotkey 'a+b'"]);
+        for bad in ["ctrl+alt+", "ctrl+shfit+p", "ctrl+k ctrl+c", ""] {
+            let errors = errors(bad);
+            assert_eq!(errors.len(), 1, "{}", bad);
+            assert!(errors[0].starts_with("pause_hotkey: "), "{}", errors[0]);
+        }
+    }
+
+    #[test]
+    fn test_check_reload_rejects_fixed_settings() {
+        let config = |extra: &str| -> Config {
+            let json = format!(r#"{{"process_name": "java", {}"independent_keys": [{{"key": "a", "interval": "1s"}}]}}"#, extra);
+            serde_json::from_str(&json).unwrap()
+        };
+        let running = config("");
+
+        // Keys and timing may change
+        assert!(running.check_reload(&config(r#""max_retries": 5, "seed": 1, "#)).is_ok());
+
+        let error = running.check_reload(&config(r#""rescan_interval": "5s", "on_target_exit": "exit", "#)).unwrap_err();
+        assert_eq!(error.to_string(), "2 configuration errors:\n  \
+            rescan_interval: cannot be changed by a reload, restart pks to apply it\n  \
+            on_target_exit: cannot be changed by a reload, restart pks to apply it");
+        for extra in [
+            r#""target_mode": "all", "#,
+            r#""target_exit_code": 42, "#,
+            r#""dry_run": true, "#,
+            r#""backend": "mock", "#,
+            r#""layout": "de", "#,
+            r#""restore_focus": false, "#,
+            r#""mock_output": "events.jsonl", "#,
+            r#""pause_hotkey": "f12", "#,
+            r#""control_socket": "/tmp/pks.sock", "#,
+            r#""select": "newest", "#,
+            r#""target": {"name": "java"}, "#,
+        ] {
+            assert!(running.check_reload(&config(extra)).is_err(), "{}", extra);
+        }
+    }
+
+    #[test]
+    fn test_validation_report_collects_every_issue() {
+        let json = r#"
+        {
+            "process_name": " ",
+            "key_sequence": [
+                {"key": "shfit+a", "interval_after": "1s"},
+                {"key": "a", "interval_after": "10ms"},
+                {"press": "'text'", "interval_after": "0ms"},
+                {"key": "b", "char_delay": "5ms", "interval_after": "100ms..200ms"}
+            ],
+            "max_retries": 0
+        }
+        "#;
+
+        let report = serde_json::from_str::<Config>(json).unwrap().check();
+        let errors: Vec<String> = report.errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+        assert_eq!(errors[0], "process_name: cannot be empty");
+        assert_eq!(errors[1], "max_retries: must be greater than 0");
+        assert!(errors[2].starts_with("key_sequence[0]: unknown key 'shfit'"));
+        assert!(errors[3].starts_with("key_sequence[2]: press 'text' takes a single key or chord"));
+        assert_eq!(errors[4], "key_sequence[2]: interval_after must be at least 1ms");
+        assert_eq!(errors[5], "key_sequence[3]: char_delay only applies to text actions");
+
+        // 0ms is an error, not also a warning
+        assert_eq!(report.warnings, vec![ValidationIssue {
+            path: "key_sequence[1]".to_string(),
+            message: "very short interval_after (10ms) may cause issues".to_string(),
+        }]);
+        assert!(!report.is_valid());
+
+        let error = report.into_result().unwrap_err().to_string();
+        assert!(error.starts_with("6 configuration errors:\n  process_name: cannot be empty\n"), "{}", error);
+    }
+
+    #[test]
+    fn test_timing_parsing() {
+        let json = r#"
+        {
+            "process_name": "game.exe",
+            "independent_keys": [{"key": "r", "interval": "900ms..1.2s"}],
+            "key_sequence": [],
+            "seed": 7,
+            "distribution": "normal",
+            "jitter": "15%",
+            "hold_time": "20ms..60ms"
+        }
+        "#;
+
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.independent_keys[0].interval, Delay::parse("900ms..1200ms").unwrap());
+        assert_eq!(config.seed, Some(7));
+        assert_eq!(config.distribution, Distribution::Normal);
+        assert_eq!(config.jitter, 15);
+        assert_eq!(config.hold_time, Some(Delay::parse("20ms..60ms").unwrap()));
+
+        // Saved settings load back unchanged
+        let saved = serde_json::to_string(&ConfigForSave::from(config.clone())).unwrap();
+        let reloaded: Config = serde_json::from_str(&saved).unwrap();
+        assert_eq!(reloaded.independent_keys[0].interval, config.independent_keys[0].interval);
+        assert_eq!((reloaded.seed, reloaded.distribution, reloaded.jitter), (Some(7), Distribution::Normal, 15));
+        assert_eq!(reloaded.hold_time, config.hold_time);
+
+        for bad in [r#""jitter": "150%""#, r#""distribution": "poisson""#, r#""hold_time": "2s..1s""#] {
+            let json = format!(r#"{{"process_name": "a", {}}}"#, bad);
+            assert!(serde_json::from_str::<Config>(&json).is_err(), "{}", bad);
+        }
     }
 
     #[test]
     fn test_config_validation() {
         let mut config = Config {
             process_name: "test.exe".to_string(),
+            target: None,
+            pid: None,
+            pidfile: None,
+            select: SelectionPolicy::LowestPid,
+            target_mode: TargetMode::Single,
+            broadcast: Broadcast::Independent,
+            rescan_interval: Delay::fixed(Duration::from_secs(2)),
+            on_target_exit: OnTargetExit::Rediscover,
+            target_exit_code: 3,
             key_sequence: vec![],
             independent_keys: vec![IndependentKey {
                 key: "r".to_string(),
-                interval: Duration::from_millis(1000),
+                interval: Duration::from_millis(1000).into(),
             }],
             max_retries: 10,
             pause_hotkey: "ctrl+alt+r".to_string(),
@@ -293,6 +1325,15 @@ mod tests {
             loop_sequence: true,
             repeat_count: 0,
             restore_focus: true,
+            backend: BackendKind::Auto,
+            mock_output: None,
+            dry_run: false,
+            control_socket: None,
+            layout: None,
+            seed: None,
+            distribution: Distribution::Uniform,
+            jitter: 0,
+            hold_time: None,
         };
 
         assert!(config.validate().is_ok());
@@ -0,0 +1,275 @@
+//! Local control socket and Unix signals for steering a running `pks`.
+//!
+//! The server listens on a Unix domain socket and speaks line-delimited JSON:
+//! each request is a line like `{"command": "pause"}` and is answered with a
+//! single [`ControlResponse`] line.
+//!
+//! The same actions are available as signals: SIGTERM stops, SIGUSR1 toggles
+//! pause and SIGHUP reloads the configuration.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::automation::StatusReport;
+
+/// A request understood by the control server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum ControlCommand {
+    Pause,
+    Resume,
+    Stop,
+    Status,
+    Reload,
+}
+
+impl ControlCommand {
+    pub const NAMES: &'static [&'static str] = &["pause", "resume", "stop", "status", "reload"];
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        serde_json::from_value(serde_json::json!({ "command": name.trim().to_lowercase() }))
+            .map_err(|_| anyhow::anyhow!(
+                "Unknown command '{}' (expected one of: {})", name, Self::NAMES.join(", ")
+            ))
+    }
+}
+
+/// The server's answer to one [`ControlCommand`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<StatusReport>,
+}
+
+impl ControlResponse {
+    fn ok(message: &str) -> Self {
+        Self {
+            ok: true,
+            message: Some(message.to_string()),
+            status: None,
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Self {
+            ok: false,
+            message: Some(message),
+            status: None,
+        }
+    }
+}
+
+/// `$XDG_RUNTIME_DIR/pks.sock`, or a file in the temp directory without one
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("pks.sock"),
+        None => std::env::temp_dir().join("pks.sock"),
+    }
+}
+
+#[cfg(unix)]
+pub use unix::{send_command, spawn_signal_handlers, ControlServer};
+
+#[cfg(not(unix))]
+pub async fn send_command(_path: &std::path::Path, _command: ControlCommand) -> Result<ControlResponse> {
+    anyhow::bail!("The control socket is only available on Unix")
+}
+
+#[cfg(unix)]
+mod unix {
+    use anyhow::Result;
+    use colored::Colorize;
+    use std::path::{Path, PathBuf};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::signal::unix::{signal, SignalKind};
+    use tokio::task::JoinHandle;
+
+    use super::{ControlCommand, ControlResponse};
+    use crate::automation::RunControl;
+
+    /// A running control server. The socket file is removed when this is dropped.
+    pub struct ControlServer {
+        path: PathBuf,
+        task: JoinHandle<()>,
+    }
+
+    impl ControlServer {
+        /// Bind `path` and start answering requests in the background
+        pub async fn start(path: &Path, control: RunControl) -> Result<Self> {
+            if path.exists() {
+                // Refuse to steal the socket of a live instance, but clean up stale ones
+                if UnixStream::connect(path).await.is_ok() {
+                    anyhow::bail!("another pks instance is already listening on {}", path.display());
+                }
+                std::fs::remove_file(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to remove stale socket '{}': {}", path.display(), e))?;
+            }
+
+            let listener = UnixListener::bind(path)
+                .map_err(|e| anyhow::anyhow!("Failed to bind control socket '{}': {}", path.display(), e))?;
+
+            let task = tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(handle_client(stream, control.clone()));
+                }
+            });
+
+            Ok(Self {
+                path: path.to_path_buf(),
+                task,
+            })
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for ControlServer {
+        fn drop(&mut self) {
+            self.task.abort();
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    async fn handle_client(stream: UnixStream, control: RunControl) {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<ControlCommand>(&line) {
+                Ok(command) => execute(command, &control).await,
+                Err(e) => ControlResponse::error(format!("invalid request: {}", e)),
+            };
+
+            let Ok(mut json) = serde_json::to_string(&response) else {
+                break;
+            };
+            json.push('\n');
+            if writer.write_all(json.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    async fn execute(command: ControlCommand, control: &RunControl) -> ControlResponse {
+        match command {
+            ControlCommand::Pause => {
+                control.pause.set_paused(true);
+                println!("{} Paused (control socket)", "⏸".yellow());
+                ControlResponse::ok("paused")
+            }
+            ControlCommand::Resume => {
+                control.pause.set_paused(false);
+                println!("{} Resumed (control socket)", "▶".green());
+                ControlResponse::ok("resumed")
+            }
+            ControlCommand::Stop => {
+                println!("\n{} Shutting down (control socket)...", "🛑".yellow());
+                control.shutdown.trigger();
+                ControlResponse::ok("stopping")
+            }
+            ControlCommand::Status => ControlResponse {
+                ok: true,
+                message: None,
+                status: Some(control.status()),
+            },
+            ControlCommand::Reload => match control.request_reload().await {
+                Ok(()) => ControlResponse::ok("configuration reloaded"),
+                Err(e) => ControlResponse::error(format!("reload failed: {}", e)),
+            },
+        }
+    }
+
+    /// Stop on SIGTERM, toggle pause on SIGUSR1 and reload on SIGHUP.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn spawn_signal_handlers(control: &RunControl) -> Result<()> {
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut user1 = signal(SignalKind::user_defined1())?;
+        let mut hangup = signal(SignalKind::hangup())?;
+
+        let shutdown = control.shutdown.clone();
+        tokio::spawn(async move {
+            if terminate.recv().await.is_some() {
+                println!("\n{} Shutting down (SIGTERM)...", "🛑".yellow());
+                shutdown.trigger();
+            }
+        });
+
+        let pause = control.pause.clone();
+        tokio::spawn(async move {
+            while user1.recv().await.is_some() {
+                if pause.toggle() {
+                    println!("{} Paused (SIGUSR1)", "⏸".yellow());
+                } else {
+                    println!("{} Resumed (SIGUSR1)", "▶".green());
+                }
+            }
+        });
+
+        let control = control.clone();
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                if let Err(e) = control.request_reload().await {
+                    eprintln!("{} Reload failed (SIGHUP): {}", "✗".red(), e);
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Send one command to the server at `path` and wait for its answer
+    pub async fn send_command(path: &Path, command: ControlCommand) -> Result<ControlResponse> {
+        let stream = UnixStream::connect(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to connect to '{}': {} (is pks running?)", path.display(), e))?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut request = serde_json::to_string(&command)?;
+        request.push('\n');
+        writer.write_all(request.as_bytes()).await?;
+
+        let line = BufReader::new(reader)
+            .lines()
+            .next_line()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Control server closed the connection without answering"))?;
+
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_wire_format() {
+        assert_eq!(serde_json::to_string(&ControlCommand::Pause).unwrap(), r#"{"command":"pause"}"#);
+        assert_eq!(
+            serde_json::from_str::<ControlCommand>(r#"{"command": "status"}"#).unwrap(),
+            ControlCommand::Status
+        );
+        assert!(serde_json::from_str::<ControlCommand>(r#"{"command": "explode"}"#).is_err());
+    }
+
+    #[test]
+    fn test_command_from_name() {
+        for name in ControlCommand::NAMES {
+            assert!(ControlCommand::from_name(name).is_ok());
+        }
+        assert_eq!(ControlCommand::from_name(" Reload ").unwrap(), ControlCommand::Reload);
+        assert!(ControlCommand::from_name("nope").is_err());
+    }
+}
@@ -0,0 +1,251 @@
+//! Global pause/resume hotkey.
+//!
+//! On Linux the hotkey is detected by reading keyboard devices through evdev,
+//! falling back to an X11 `XGrabKey` grab when no input device is readable.
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::automation::Pause;
+use crate::keys::KeyExpr;
+
+/// A modifier that must be held for the hotkey to fire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    Ctrl,
+    Alt,
+    Shift,
+    Super,
+}
+
+/// A parsed hotkey such as `ctrl+alt+r`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: Vec<Modifier>,
+    pub key: String,
+}
+
+impl Hotkey {
+    /// Parse a hotkey using the same `mod+mod+key` syntax as key combinations
+    pub fn parse(hotkey: &str) -> Result<Self> {
+        let mut keys = match KeyExpr::parse(hotkey)? {
+            KeyExpr::Key(key) => vec![key],
+            KeyExpr::Chord(chord) => chord.keys,
+            _ => anyhow::bail!("Hotkey '{}' must be a single key or key combination", hotkey),
+        };
+        // The parser never returns an empty chord
+        let key = keys.pop().unwrap();
+        let modifier_names = keys;
+
+        let mut modifiers = Vec::new();
+        for name in modifier_names {
+            // Either side of a modifier fires the hotkey, so lctrl is the same as ctrl
+            let modifier = match name.as_str() {
+                "ctrl" | "lctrl" | "rctrl" => Modifier::Ctrl,
+                "alt" | "lalt" | "ralt" => Modifier::Alt,
+                "shift" | "lshift" | "rshift" => Modifier::Shift,
+                "super" | "lsuper" | "rsuper" => Modifier::Super,
+                _ => anyhow::bail!("'{}' is not a modifier in hotkey '{}'", name, hotkey),
+            };
+            if !modifiers.contains(&modifier) {
+                modifiers.push(modifier);
+            }
+        }
+
+        Ok(Self { modifiers, key })
+    }
+}
+
+/// Flip `pause` and tell the user about it
+fn toggle_pause(pause: &Pause, hotkey: &str) {
+    if pause.toggle() {
+        println!("{} Paused (press {} to resume)", "⏸".yellow(), hotkey.cyan());
+    } else {
+        println!("{} Resumed", "▶".green());
+    }
+}
+
+/// Start listening for `hotkey` in the background, toggling `pause` on each press.
+///
+/// Returns a short description of how the hotkey is being detected.
+#[cfg(target_os = "linux")]
+pub fn spawn_pause_listener(hotkey: &str, pause: Pause) -> Result<String> {
+    let parsed = Hotkey::parse(hotkey)?;
+
+    match linux::spawn_evdev_listener(&parsed, hotkey, pause.clone()) {
+        Ok(description) => Ok(description),
+        Err(evdev_error) => {
+            if std::env::var_os("DISPLAY").is_none() {
+                return Err(evdev_error);
+            }
+            linux::spawn_x11_listener(&parsed, hotkey, pause)
+                .map_err(|x11_error| anyhow::anyhow!("{}; X11 fallback failed: {}", evdev_error, x11_error))
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn spawn_pause_listener(hotkey: &str, _pause: Pause) -> Result<String> {
+    Hotkey::parse(hotkey)?;
+    anyhow::bail!("The pause hotkey is not supported on this platform yet")
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use anyhow::Result;
+    use evdev::{InputEventKind, Key};
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::Event;
+    use x11rb::protocol::xproto::{ConnectionExt as _, GrabMode, ModMask};
+
+    use super::{toggle_pause, Hotkey, Modifier};
+    use crate::automation::Pause;
+    use crate::backend::uinput::{build_key_map, DEVICE_NAME};
+    use crate::backend::x11::X11Session;
+
+    fn modifier_keys(modifier: Modifier) -> [Key; 2] {
+        match modifier {
+            Modifier::Ctrl => [Key::KEY_LEFTCTRL, Key::KEY_RIGHTCTRL],
+            Modifier::Alt => [Key::KEY_LEFTALT, Key::KEY_RIGHTALT],
+            Modifier::Shift => [Key::KEY_LEFTSHIFT, Key::KEY_RIGHTSHIFT],
+            Modifier::Super => [Key::KEY_LEFTMETA, Key::KEY_RIGHTMETA],
+        }
+    }
+
+    /// Watch every readable keyboard in `/dev/input`, one thread per device
+    pub(super) fn spawn_evdev_listener(hotkey: &Hotkey, name: &str, pause: Pause) -> Result<String> {
+        let main_key = *build_key_map()
+            .get(&hotkey.key)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported hotkey key: {}", hotkey.key))?;
+
+        // Held keys are shared so modifiers on one keyboard combine with another
+        let held = Arc::new(Mutex::new(HashSet::new()));
+        let mut keyboards = 0;
+
+        // enumerate() only yields devices we are allowed to open
+        for (_, mut device) in evdev::enumerate() {
+            // Ignore our own virtual keyboard and anything that can't type the key
+            if device.name() == Some(DEVICE_NAME) {
+                continue;
+            }
+            if !device.supported_keys().is_some_and(|keys| keys.contains(main_key)) {
+                continue;
+            }
+
+            keyboards += 1;
+
+            let held = held.clone();
+            let pause = pause.clone();
+            let modifiers = hotkey.modifiers.clone();
+            let name = name.to_string();
+
+            std::thread::spawn(move || {
+                while let Ok(events) = device.fetch_events() {
+                    for event in events {
+                        let InputEventKind::Key(key) = event.kind() else {
+                            continue;
+                        };
+
+                        let mut held = held.lock().unwrap();
+                        match event.value() {
+                            1 => {
+                                held.insert(key);
+                                let modifiers_held = modifiers
+                                    .iter()
+                                    .all(|&m| modifier_keys(m).iter().any(|k| held.contains(k)));
+                                if key == main_key && modifiers_held {
+                                    toggle_pause(&pause, &name);
+                                }
+                            }
+                            0 => {
+                                held.remove(&key);
+                            }
+                            // Auto-repeat must not toggle again
+                            _ => {}
+                        }
+                    }
+                }
+            });
+        }
+
+        if keyboards == 0 {
+            anyhow::bail!("no readable keyboard in /dev/input (is your user in the 'input' group?)");
+        }
+
+        Ok(format!("evdev, {} keyboard(s)", keyboards))
+    }
+
+    /// Grab the hotkey on the X root window
+    pub(super) fn spawn_x11_listener(hotkey: &Hotkey, name: &str, pause: Pause) -> Result<String> {
+        let session = X11Session::connect()?;
+        let keycode = session.keycode(&hotkey.key)?;
+
+        let mut modifiers = 0u16;
+        for modifier in &hotkey.modifiers {
+            modifiers |= u16::from(match modifier {
+                Modifier::Ctrl => ModMask::CONTROL,
+                Modifier::Alt => ModMask::M1,
+                Modifier::Shift => ModMask::SHIFT,
+                Modifier::Super => ModMask::M4,
+            });
+        }
+
+        // Grabs match modifiers exactly, so also grab with Caps Lock and Num Lock on
+        let lock = u16::from(ModMask::LOCK);
+        let num_lock = u16::from(ModMask::M2);
+        for extra in [0, lock, num_lock, lock | num_lock] {
+            session
+                .conn
+                .grab_key(true, session.root, ModMask::from(modifiers | extra), keycode, GrabMode::ASYNC, GrabMode::ASYNC)?
+                .check()
+                .map_err(|e| anyhow::anyhow!("could not grab '{}', is another program using it? ({})", name, e))?;
+        }
+
+        let name = name.to_string();
+        std::thread::spawn(move || {
+            while let Ok(event) = session.conn.wait_for_event() {
+                if let Event::KeyPress(_) = event {
+                    toggle_pause(&pause, &name);
+                }
+            }
+        });
+
+        Ok("X11 key grab".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hotkey_parse() {
+        let hotkey = Hotkey::parse("ctrl+alt+r").unwrap();
+        assert_eq!(hotkey.modifiers, vec![Modifier::Ctrl, Modifier::Alt]);
+        assert_eq!(hotkey.key, "r");
+
+        let hotkey = Hotkey::parse("Shift+F12").unwrap();
+        assert_eq!(hotkey.modifiers, vec![Modifier::Shift]);
+        assert_eq!(hotkey.key, "f12");
+
+        assert_eq!(Hotkey::parse("pause").unwrap().modifiers, vec![]);
+
+        let hotkey = Hotkey::parse("lctrl+rshift+ralt+p").unwrap();
+        assert_eq!(hotkey.modifiers, vec![Modifier::Ctrl, Modifier::Shift, Modifier::Alt]);
+        assert_eq!(Hotkey::parse("rctrl+ctrl+p").unwrap().modifiers, vec![Modifier::Ctrl]);
+        assert_eq!(Hotkey::parse("lalt+lshift+p").unwrap().modifiers, vec![Modifier::Alt, Modifier::Shift]);
+
+        for hotkey in ["super+p", "win+p", "lsuper+p", "rsuper+p"] {
+            assert_eq!(Hotkey::parse(hotkey).unwrap().modifiers, vec![Modifier::Super], "{}", hotkey);
+        }
+    }
+
+    #[test]
+    fn test_hotkey_parse_errors() {
+        assert!(Hotkey::parse("").is_err());
+        assert!(Hotkey::parse("ctrl+").is_err());
+        assert!(Hotkey::parse("a+b").is_err());
+    }
+}
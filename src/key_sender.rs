@@ -1,375 +1,285 @@
 use anyhow::Result;
-use std::collections::HashMap;
-
-#[cfg(windows)]
-use winapi::um::winuser::{
-    VK_SPACE, VK_RETURN, VK_TAB, VK_ESCAPE, VK_SHIFT, VK_CONTROL, VK_MENU,
-    EnumWindows, GetWindowThreadProcessId, IsWindowVisible, GetWindowTextA,
-    SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP,
-    SetForegroundWindow, SetActiveWindow, BringWindowToTop, ShowWindow,
-    SW_RESTORE, GetForegroundWindow
-};
-#[cfg(windows)]
-use winapi::shared::windef::HWND;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use crate::backend::{self, BackendKind, BackendOptions, DeliveryReport, DryRunBackend, InputBackend, MouseButton, MouseMotion};
+use crate::config::Config;
+use crate::keys::{KeyExpr, Stroke};
+use crate::timing::Timing;
+
+/// Sends key strings such as `"space"` or `"ctrl+s"` through an [`InputBackend`]
+#[derive(Clone)]
 pub struct KeySender {
-    #[cfg(windows)]
-    key_map: HashMap<String, u32>,
+    backend: Arc<dyn InputBackend>,
+    /// Keys and buttons pressed and not yet released, in press order
+    held: Arc<Mutex<Vec<(u64, Held)>>>,
+    /// Draws how long each tap holds its key down
+    timing: Arc<Mutex<Timing>>,
 }
 
-impl Clone for KeySender {
-    fn clone(&self) -> Self {
-        Self::new().unwrap()
-    }
+/// A key or mouse button left down by [`KeySender`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Held {
+    Key(String),
+    Button(MouseButton),
 }
 
 impl KeySender {
+    /// Create a key sender using the default backend for this platform
     pub fn new() -> Result<Self> {
-        #[cfg(windows)]
-        {
-            let mut key_map = HashMap::new();
-
-            // Special keys
-            key_map.insert("space".to_string(), VK_SPACE as u32);
-            key_map.insert("enter".to_string(), VK_RETURN as u32);
-            key_map.insert("return".to_string(), VK_RETURN as u32);
-            key_map.insert("tab".to_string(), VK_TAB as u32);
-            key_map.insert("escape".to_string(), VK_ESCAPE as u32);
-            key_map.insert("esc".to_string(), VK_ESCAPE as u32);
-            key_map.insert("shift".to_string(), VK_SHIFT as u32);
-            key_map.insert("ctrl".to_string(), VK_CONTROL as u32);
-            key_map.insert("control".to_string(), VK_CONTROL as u32);
-            key_map.insert("alt".to_string(), VK_MENU as u32);
-
-            // Function keys
-            for i in 1..=12 {
-                key_map.insert(format!("f{}", i), (0x70 + i - 1) as u32);
-            }
-
-            // Number keys
-            for i in 0..=9 {
-                key_map.insert(i.to_string(), (0x30 + i) as u32);
-            }
-
-            // Letter keys
-            for i in 0..26 {
-                let letter = (b'a' + i) as char;
-                key_map.insert(letter.to_string(), (0x41 + i) as u32); // VK_A to VK_Z
-            }
+        Self::with_kind(BackendKind::Auto, &BackendOptions::default())
+    }
 
-            // Arrow keys
-            key_map.insert("left".to_string(), 0x25);
-            key_map.insert("up".to_string(), 0x26);
-            key_map.insert("right".to_string(), 0x27);
-            key_map.insert("down".to_string(), 0x28);
-
-            // Additional keys
-            key_map.insert("backspace".to_string(), 0x08);
-            key_map.insert("delete".to_string(), 0x2E);
-            key_map.insert("home".to_string(), 0x24);
-            key_map.insert("end".to_string(), 0x23);
-            key_map.insert("pageup".to_string(), 0x21);
-            key_map.insert("pagedown".to_string(), 0x22);
-
-            Ok(Self { key_map })
+    /// Create a key sender using the backend selected in `config`
+    pub fn from_config(config: &Config) -> Result<Self> {
+        // A dry run must never touch the real input system, but still only
+        // accepts what the real backend could send
+        if config.dry_run {
+            return Ok(Self::with_backend(Arc::new(DryRunBackend::new(config.backend))));
         }
 
-        #[cfg(unix)]
-        {
-            Ok(Self)
-        }
+        let options = BackendOptions {
+            restore_focus: config.restore_focus,
+            mock_output: config.mock_output.as_ref().map(PathBuf::from),
+            layout: config.layout,
+        };
+        Self::with_kind(config.backend, &options)
     }
 
-    pub fn parse_key_for_validation(&self, key: &str) -> Result<()> {
-        #[cfg(windows)]
-        {
-            let _ = self.parse_key_windows(key)?;
-            Ok(())
-        }
+    pub fn with_kind(kind: BackendKind, options: &BackendOptions) -> Result<Self> {
+        Ok(Self::with_backend(backend::create_backend(kind, options)?))
+    }
 
-        #[cfg(unix)]
-        {
-            if key.trim().is_empty() {
-                anyhow::bail!("Key cannot be empty");
-            }
-            Ok(())
+    /// Wrap an existing backend, e.g. a test double
+    pub fn with_backend(backend: Arc<dyn InputBackend>) -> Self {
+        Self {
+            backend,
+            held: Arc::new(Mutex::new(Vec::new())),
+            timing: Arc::new(Mutex::new(Timing::default())),
         }
     }
 
-    pub fn send_key_to_window(&self, window_id: u64, key: &str) -> Result<()> {
-        #[cfg(windows)]
-        {
-            let pid = window_id as u32;
-
-            // Try to find the actual window handle
-            if let Some(hwnd) = self.find_window_by_pid(pid) {
-                // Method: Focus window temporarily, send key, restore focus
-                self.send_key_with_focus_restore(hwnd, key)
-            } else {
-                // Fallback: Global SendInput
-                self.send_key_global_windows(key)
-            }
-        }
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
 
-        #[cfg(unix)]
-        {
-            self.send_key_unix(window_id, key)
-        }
+    /// Take tap and click hold times from `timing` from now on, in every clone
+    /// of this sender
+    pub fn set_timing(&self, timing: Timing) {
+        *self.timing.lock().unwrap() = timing;
     }
 
-    #[cfg(windows)]
-    fn find_window_by_pid(&self, target_pid: u32) -> Option<HWND> {
-        struct EnumData {
-            target_pid: u32,
-            result: Option<HWND>,
+    /// Check that `key` is a valid key expression whose keys the backend can send
+    pub fn parse_key_for_validation(&self, key: &str) -> Result<()> {
+        let expr = KeyExpr::parse(key)?;
+        if let KeyExpr::Text(text) = &expr {
+            return self.validate_text(text);
         }
 
-        let mut enum_data = EnumData {
-            target_pid,
-            result: None,
-        };
-
-        unsafe extern "system" fn enum_proc(hwnd: HWND, lparam: isize) -> i32 {
-            let enum_data = &mut *(lparam as *mut EnumData);
-
-            unsafe {
-                let mut window_pid = 0;
-                GetWindowThreadProcessId(hwnd, &mut window_pid);
+        for stroke in expr.strokes(self.backend.layout()) {
+            if let Stroke::Chord(chord) = stroke {
+                for part in &chord.keys {
+                    self.backend.validate_key(self.physical_key(part))?;
+                }
+            }
+        }
 
-                if window_pid == enum_data.target_pid && IsWindowVisible(hwnd) != 0 {
-                    let mut title = [0u8; 256];
-                    let len = GetWindowTextA(hwnd, title.as_mut_ptr() as *mut i8, 256);
+        Ok(())
+    }
 
-                    if len > 0 {
-                        enum_data.result = Some(hwnd);
-                        return 0; // Stop enumeration
+    /// Check that the backend can type `text` on its layout, through its
+    /// Unicode input method for characters that have no key there
+    pub fn validate_text(&self, text: &str) -> Result<()> {
+        for stroke in KeyExpr::Text(text.to_string()).strokes(self.backend.layout()) {
+            match stroke {
+                Stroke::Chord(chord) => {
+                    for key in &chord.keys {
+                        self.backend.validate_key(key)?;
                     }
                 }
+                Stroke::Unicode(c) => self.backend.validate_unicode(c)?,
             }
-
-            1 // Continue enumeration
         }
 
-        unsafe {
-            EnumWindows(Some(enum_proc), &mut enum_data as *mut _ as isize);
-        }
-
-        enum_data.result
+        Ok(())
     }
 
-    #[cfg(windows)]
-    fn send_key_with_focus_restore(&self, hwnd: HWND, key: &str) -> Result<()> {
-        // Store current foreground window to restore later
-        let original_window = unsafe { GetForegroundWindow() };
+    /// Ask the backend whether keys are expected to reach each of the target's windows
+    pub fn delivery_report(&self, window_id: u64) -> Result<Vec<DeliveryReport>> {
+        self.backend.delivery_report(window_id)
+    }
 
-        // Only change focus if the target window is not already focused
-        let needs_focus_change = original_window != hwnd;
+    /// Send a key expression such as `"space"`, `"ctrl+s"` or `"ctrl+k ctrl+c"`
+    pub fn send_key_to_window(&self, window_id: u64, key: &str) -> Result<()> {
+        let expr = KeyExpr::parse(key)?;
 
-        if needs_focus_change {
-            // Bring target window to foreground
-            self.ensure_window_focus(hwnd)?;
+        if let KeyExpr::Text(text) = &expr {
+            return text.chars().try_for_each(|c| self.type_char(window_id, c));
         }
 
-        // Send the key using global SendInput
-        let result = self.send_key_global_windows(key);
-
-        // Restore original window focus if we changed it
-        if needs_focus_change && !original_window.is_null() {
-            // Small delay to ensure the key is processed
-            std::thread::sleep(std::time::Duration::from_millis(50));
-
-            // Restore focus to original window
-            unsafe {
-                SetForegroundWindow(original_window);
-                SetActiveWindow(original_window);
+        for stroke in expr.strokes(self.backend.layout()) {
+            // Only text has characters without a key, and it was typed above
+            let Stroke::Chord(chord) = stroke else { continue };
+            let keys: Vec<&str> = chord.keys.iter().map(|key| self.physical_key(key)).collect();
+            if let [key] = keys[..] {
+                self.backend.tap(window_id, key, self.hold())?;
+            } else {
+                self.backend.chord(window_id, &keys, self.hold())?;
             }
         }
 
-        result
+        Ok(())
     }
 
-    #[cfg(windows)]
-    fn ensure_window_focus(&self, hwnd: HWND) -> Result<()> {
-        unsafe {
-            // Restore window if minimized
-            ShowWindow(hwnd, SW_RESTORE);
-
-            // Bring to top and set focus
-            BringWindowToTop(hwnd);
-            SetActiveWindow(hwnd);
-            SetForegroundWindow(hwnd);
+    /// Type one character, holding shift as needed
+    pub fn type_char(&self, window_id: u64, c: char) -> Result<()> {
+        self.backend.type_char(window_id, c, self.hold())
+    }
 
-            // Minimal delay to ensure focus is established
-            std::thread::sleep(std::time::Duration::from_millis(50));
-        }
+    /// Press `key` and keep it down until [`release_key`](Self::release_key)
+    /// or [`release_held_keys`](Self::release_held_keys)
+    pub fn press_key(&self, window_id: u64, key: &str) -> Result<()> {
+        let key = self.physical_key(key);
+        self.backend.press(window_id, key)?;
+        self.held.lock().unwrap().push((window_id, Held::Key(key.to_string())));
         Ok(())
     }
 
-    #[cfg(windows)]
-    fn send_key_global_windows(&self, key: &str) -> Result<()> {
-        if key.contains('+') {
-            return self.send_key_combination_global_windows(key);
-        }
-
-        let vk_code = self.parse_key_windows(key)?;
-
-        unsafe {
-            // Key DOWN
-            let mut input_down = INPUT {
-                type_: INPUT_KEYBOARD,
-                u: std::mem::zeroed(),
-            };
+    pub fn release_key(&self, window_id: u64, key: &str) -> Result<()> {
+        let key = self.physical_key(key);
+        self.forget_held(window_id, &Held::Key(key.to_string()));
+        self.backend.release(window_id, key)
+    }
 
-            *input_down.u.ki_mut() = KEYBDINPUT {
-                wVk: vk_code as u16,
-                wScan: 0,
-                dwFlags: 0, // Key down
-                time: 0,
-                dwExtraInfo: 0,
-            };
+    /// Press every key of a key or chord such as `"shift"` or `"ctrl+shift"`
+    /// and keep them down until [`release_keys`](Self::release_keys)
+    pub fn press_keys(&self, window_id: u64, key: &str) -> Result<()> {
+        let keys = chord_keys(key)?;
 
-            // Key UP
-            let mut input_up = INPUT {
-                type_: INPUT_KEYBOARD,
-                u: std::mem::zeroed(),
-            };
+        for (i, part) in keys.iter().enumerate() {
+            if let Err(e) = self.press_key(window_id, part) {
+                // Don't leave half a chord held
+                for pressed in keys[..i].iter().rev() {
+                    let _ = self.release_key(window_id, pressed);
+                }
+                return Err(e);
+            }
+        }
 
-            *input_up.u.ki_mut() = KEYBDINPUT {
-                wVk: vk_code as u16,
-                wScan: 0,
-                dwFlags: KEYEVENTF_KEYUP,
-                time: 0,
-                dwExtraInfo: 0,
-            };
+        Ok(())
+    }
 
-            // Send key down
-            let result1 = SendInput(1, &mut input_down, std::mem::size_of::<INPUT>() as i32);
+    /// Release the keys of a key or chord pressed with [`press_keys`](Self::press_keys),
+    /// in reverse order
+    pub fn release_keys(&self, window_id: u64, key: &str) -> Result<()> {
+        let mut result = Ok(());
+        for part in chord_keys(key)?.iter().rev() {
+            result = result.and(self.release_key(window_id, part));
+        }
+        result
+    }
 
-            // Realistic key press duration
-            std::thread::sleep(std::time::Duration::from_millis(30));
+    /// Check that the backend can send mouse input, including moves to
+    /// screen coordinates when `absolute`
+    pub fn validate_mouse(&self, absolute: bool) -> Result<()> {
+        self.backend.validate_mouse(absolute)
+    }
 
-            // Send key up
-            let result2 = SendInput(1, &mut input_up, std::mem::size_of::<INPUT>() as i32);
+    pub fn move_mouse(&self, window_id: u64, motion: MouseMotion) -> Result<()> {
+        self.backend.mouse_move(window_id, motion)
+    }
 
-            if result1 == 0 || result2 == 0 {
-                anyhow::bail!("SendInput failed for key '{}' (results: {}, {})", key, result1, result2);
-            }
-        }
+    pub fn click(&self, window_id: u64, button: MouseButton) -> Result<()> {
+        self.backend.click(window_id, button, self.hold())
+    }
 
+    /// Press `button` and keep it down until [`release_button`](Self::release_button)
+    /// or [`release_held_keys`](Self::release_held_keys)
+    pub fn press_button(&self, window_id: u64, button: MouseButton) -> Result<()> {
+        self.backend.mouse_button(window_id, button, true)?;
+        self.held.lock().unwrap().push((window_id, Held::Button(button)));
         Ok(())
     }
 
-    #[cfg(windows)]
-    fn send_key_combination_global_windows(&self, key_combo: &str) -> Result<()> {
-        let parts: Vec<&str> = key_combo.split('+').map(|s| s.trim()).collect();
-        if parts.len() < 2 {
-            anyhow::bail!("Invalid key combination format: {}", key_combo);
-        }
+    pub fn release_button(&self, window_id: u64, button: MouseButton) -> Result<()> {
+        self.forget_held(window_id, &Held::Button(button));
+        self.backend.mouse_button(window_id, button, false)
+    }
 
-        let mut modifier_codes = Vec::new();
-        let main_key = parts.last().unwrap();
+    /// Scroll by whole notches; positive `dy` scrolls down, positive `dx` right
+    pub fn scroll(&self, window_id: u64, dx: i32, dy: i32) -> Result<()> {
+        self.backend.scroll(window_id, dx, dy)
+    }
 
-        // Parse modifiers
-        for modifier in &parts[..parts.len() - 1] {
-            let vk_code = self.parse_key_windows(modifier)?;
-            modifier_codes.push(vk_code);
-        }
+    fn hold(&self) -> Duration {
+        self.timing.lock().unwrap().hold()
+    }
 
-        let main_key_code = self.parse_key_windows(main_key)?;
-
-        unsafe {
-            let mut inputs = Vec::new();
-
-            // Press modifiers
-            for &modifier_code in &modifier_codes {
-                let mut input = INPUT {
-                    type_: INPUT_KEYBOARD,
-                    u: std::mem::zeroed(),
-                };
-                *input.u.ki_mut() = KEYBDINPUT {
-                    wVk: modifier_code as u16,
-                    wScan: 0,
-                    dwFlags: 0,
-                    time: 0,
-                    dwExtraInfo: 0,
-                };
-                inputs.push(input);
-            }
+    /// The key the backend must send for `key` on its layout
+    fn physical_key<'a>(&self, key: &'a str) -> &'a str {
+        self.backend.layout().physical_key(key)
+    }
 
-            // Press main key
-            let mut main_down = INPUT {
-                type_: INPUT_KEYBOARD,
-                u: std::mem::zeroed(),
-            };
-            *main_down.u.ki_mut() = KEYBDINPUT {
-                wVk: main_key_code as u16,
-                wScan: 0,
-                dwFlags: 0,
-                time: 0,
-                dwExtraInfo: 0,
-            };
-            inputs.push(main_down);
+    /// Keys and mouse buttons currently held down by [`press_key`](Self::press_key)
+    /// or [`press_button`](Self::press_button)
+    pub fn held_keys(&self) -> Vec<(u64, Held)> {
+        self.held.lock().unwrap().clone()
+    }
 
-            // Release main key
-            let mut main_up = INPUT {
-                type_: INPUT_KEYBOARD,
-                u: std::mem::zeroed(),
-            };
-            *main_up.u.ki_mut() = KEYBDINPUT {
-                wVk: main_key_code as u16,
-                wScan: 0,
-                dwFlags: KEYEVENTF_KEYUP,
-                time: 0,
-                dwExtraInfo: 0,
-            };
-            inputs.push(main_up);
-
-            // Release modifiers (reverse order)
-            for &modifier_code in modifier_codes.iter().rev() {
-                let mut input = INPUT {
-                    type_: INPUT_KEYBOARD,
-                    u: std::mem::zeroed(),
-                };
-                *input.u.ki_mut() = KEYBDINPUT {
-                    wVk: modifier_code as u16,
-                    wScan: 0,
-                    dwFlags: KEYEVENTF_KEYUP,
-                    time: 0,
-                    dwExtraInfo: 0,
-                };
-                inputs.push(input);
-            }
+    /// Release every held key and button, most recently pressed first.
+    ///
+    /// Everything is attempted even if one fails; the first error is returned.
+    pub fn release_held_keys(&self) -> Result<()> {
+        let held = std::mem::take(&mut *self.held.lock().unwrap());
+        self.release_entries(held)
+    }
+
+    /// Release what is held in `window_id` only, leaving other windows alone
+    pub fn release_held_keys_on(&self, window_id: u64) -> Result<()> {
+        let held = {
+            let mut held = self.held.lock().unwrap();
+            let (on_window, others) = held.drain(..).partition(|(w, _)| *w == window_id);
+            *held = others;
+            on_window
+        };
+        self.release_entries(held)
+    }
 
-            // Send all inputs at once
-            let result = SendInput(
-                inputs.len() as u32,
-                inputs.as_mut_ptr(),
-                std::mem::size_of::<INPUT>() as i32
-            );
+    /// Release `held`, most recently pressed first, trying every entry
+    fn release_entries(&self, held: Vec<(u64, Held)>) -> Result<()> {
+        let mut first_error = None;
 
-            if result != inputs.len() as u32 {
-                anyhow::bail!("SendInput failed for key combination '{}' (sent {}/{})", 
-                    key_combo, result, inputs.len());
+        for (window_id, held) in held.iter().rev() {
+            let result = match held {
+                Held::Key(key) => self.backend.release(*window_id, key),
+                Held::Button(button) => self.backend.mouse_button(*window_id, *button, false),
+            };
+            if let Err(e) = result {
+                first_error.get_or_insert(e);
             }
         }
 
-        Ok(())
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
     }
 
-    #[cfg(windows)]
-    fn parse_key_windows(&self, key: &str) -> Result<u32> {
-        let key_lower = key.to_lowercase();
-
-        // Check map first
-        if let Some(&vk_code) = self.key_map.get(&key_lower) {
-            return Ok(vk_code);
+    /// Drop the most recent matching entry from the held list
+    fn forget_held(&self, window_id: u64, released: &Held) {
+        let mut held = self.held.lock().unwrap();
+        if let Some(i) = held.iter().rposition(|(w, h)| *w == window_id && h == released) {
+            held.remove(i);
         }
-
-        anyhow::bail!("Unsupported key: {}", key)
     }
+}
 
-    #[cfg(unix)]
-    fn send_key_unix(&self, _window_id: u64, _key: &str) -> Result<()> {
-        anyhow::bail!("Unix key sending not yet implemented")
+/// The keys of a single key or chord expression
+fn chord_keys(key: &str) -> Result<Vec<String>> {
+    match KeyExpr::parse(key)? {
+        KeyExpr::Key(key) => Ok(vec![key]),
+        KeyExpr::Chord(chord) => Ok(chord.keys),
+        _ => anyhow::bail!("'{}' is not a single key or chord", key),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,582 @@
+//! Key expressions: the strings accepted wherever a key is configured.
+//!
+//! ```text
+//! expr     := text | sequence
+//! text     := "'" character+ "'"
+//! sequence := chord (whitespace+ chord)*
+//! chord    := key (whitespace* "+" whitespace* key)*
+//! key      := name | raw
+//! name     := a name from the key table, case-insensitive
+//! raw      := ("sc" | "vk" | "evdev" | "keysym") ":" number
+//! ```
+//!
+//! So `"space"` is a single key, `"ctrl+shift+s"` a chord, `"ctrl+k ctrl+c"`
+//! a sequence of chords pressed one after another and `"'hello'"` literal text.
+//! Parsing doesn't depend on the platform or input backend, but a [`RawKey`]
+//! such as `"vk:0x41"` is only accepted by the backends for its platform.
+
+use std::fmt;
+
+use crate::layout::Layout;
+
+/// One key every backend knows, with its code on each platform.
+///
+/// Keeping the codes here means a name is either accepted everywhere or
+/// nowhere, and `pks keys` can list exactly what the parser accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyDef {
+    /// Heading the key is listed under by `pks keys`
+    pub group: &'static str,
+    /// Canonical name, what the parser turns every alias into
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Linux input event code (`KEY_*` in `linux/input-event-codes.h`)
+    pub evdev: u16,
+    /// Windows virtual-key code (`VK_*`)
+    pub vk: u16,
+    /// X11 keysym (`X11/keysymdef.h`, `XF86keysym.h` for media keys)
+    pub keysym: u32,
+}
+
+const fn key(
+    group: &'static str,
+    name: &'static str,
+    aliases: &'static [&'static str],
+    evdev: u16,
+    vk: u16,
+    keysym: u32,
+) -> KeyDef {
+    KeyDef { group, name, aliases, evdev, vk, keysym }
+}
+
+/// Every key name accepted in key expressions. Letter, digit and punctuation
+/// codes are those of the US layout; Windows has no separate numpad enter,
+/// so `kpenter` sends `VK_RETURN` there.
+pub const KEYS: &[KeyDef] = &[
+    key("Letters", "a", &[], 30, 0x41, 0x61),
+    key("Letters", "b", &[], 48, 0x42, 0x62),
+    key("Letters", "c", &[], 46, 0x43, 0x63),
+    key("Letters", "d", &[], 32, 0x44, 0x64),
+    key("Letters", "e", &[], 18, 0x45, 0x65),
+    key("Letters", "f", &[], 33, 0x46, 0x66),
+    key("Letters", "g", &[], 34, 0x47, 0x67),
+    key("Letters", "h", &[], 35, 0x48, 0x68),
+    key("Letters", "i", &[], 23, 0x49, 0x69),
+    key("Letters", "j", &[], 36, 0x4A, 0x6a),
+    key("Letters", "k", &[], 37, 0x4B, 0x6b),
+    key("Letters", "l", &[], 38, 0x4C, 0x6c),
+    key("Letters", "m", &[], 50, 0x4D, 0x6d),
+    key("Letters", "n", &[], 49, 0x4E, 0x6e),
+    key("Letters", "o", &[], 24, 0x4F, 0x6f),
+    key("Letters", "p", &[], 25, 0x50, 0x70),
+    key("Letters", "q", &[], 16, 0x51, 0x71),
+    key("Letters", "r", &[], 19, 0x52, 0x72),
+    key("Letters", "s", &[], 31, 0x53, 0x73),
+    key("Letters", "t", &[], 20, 0x54, 0x74),
+    key("Letters", "u", &[], 22, 0x55, 0x75),
+    key("Letters", "v", &[], 47, 0x56, 0x76),
+    key("Letters", "w", &[], 17, 0x57, 0x77),
+    key("Letters", "x", &[], 45, 0x58, 0x78),
+    key("Letters", "y", &[], 21, 0x59, 0x79),
+    key("Letters", "z", &[], 44, 0x5A, 0x7a),
+    key("Digits", "0", &[], 11, 0x30, 0x30),
+    key("Digits", "1", &[], 2, 0x31, 0x31),
+    key("Digits", "2", &[], 3, 0x32, 0x32),
+    key("Digits", "3", &[], 4, 0x33, 0x33),
+    key("Digits", "4", &[], 5, 0x34, 0x34),
+    key("Digits", "5", &[], 6, 0x35, 0x35),
+    key("Digits", "6", &[], 7, 0x36, 0x36),
+    key("Digits", "7", &[], 8, 0x37, 0x37),
+    key("Digits", "8", &[], 9, 0x38, 0x38),
+    key("Digits", "9", &[], 10, 0x39, 0x39),
+    key("Function keys", "f1", &[], 59, 0x70, 0xffbe),
+    key("Function keys", "f2", &[], 60, 0x71, 0xffbf),
+    key("Function keys", "f3", &[], 61, 0x72, 0xffc0),
+    key("Function keys", "f4", &[], 62, 0x73, 0xffc1),
+    key("Function keys", "f5", &[], 63, 0x74, 0xffc2),
+    key("Function keys", "f6", &[], 64, 0x75, 0xffc3),
+    key("Function keys", "f7", &[], 65, 0x76, 0xffc4),
+    key("Function keys", "f8", &[], 66, 0x77, 0xffc5),
+    key("Function keys", "f9", &[], 67, 0x78, 0xffc6),
+    key("Function keys", "f10", &[], 68, 0x79, 0xffc7),
+    key("Function keys", "f11", &[], 87, 0x7A, 0xffc8),
+    key("Function keys", "f12", &[], 88, 0x7B, 0xffc9),
+    key("Function keys", "f13", &[], 183, 0x7C, 0xffca),
+    key("Function keys", "f14", &[], 184, 0x7D, 0xffcb),
+    key("Function keys", "f15", &[], 185, 0x7E, 0xffcc),
+    key("Function keys", "f16", &[], 186, 0x7F, 0xffcd),
+    key("Function keys", "f17", &[], 187, 0x80, 0xffce),
+    key("Function keys", "f18", &[], 188, 0x81, 0xffcf),
+    key("Function keys", "f19", &[], 189, 0x82, 0xffd0),
+    key("Function keys", "f20", &[], 190, 0x83, 0xffd1),
+    key("Function keys", "f21", &[], 191, 0x84, 0xffd2),
+    key("Function keys", "f22", &[], 192, 0x85, 0xffd3),
+    key("Function keys", "f23", &[], 193, 0x86, 0xffd4),
+    key("Function keys", "f24", &[], 194, 0x87, 0xffd5),
+    key("Modifiers", "shift", &[], 42, 0x10, 0xffe1),
+    key("Modifiers", "ctrl", &["control"], 29, 0x11, 0xffe3),
+    key("Modifiers", "alt", &[], 56, 0x12, 0xffe9),
+    key("Modifiers", "super", &["win", "windows", "meta", "cmd"], 125, 0x5B, 0xffeb),
+    key("Modifiers", "lshift", &["leftshift"], 42, 0xA0, 0xffe1),
+    key("Modifiers", "rshift", &["rightshift"], 54, 0xA1, 0xffe2),
+    key("Modifiers", "lctrl", &["leftctrl", "lcontrol"], 29, 0xA2, 0xffe3),
+    key("Modifiers", "rctrl", &["rightctrl", "rcontrol"], 97, 0xA3, 0xffe4),
+    key("Modifiers", "lalt", &["leftalt"], 56, 0xA4, 0xffe9),
+    key("Modifiers", "ralt", &["rightalt", "altgr"], 100, 0xA5, 0xffea),
+    key("Modifiers", "lsuper", &["leftsuper", "lwin"], 125, 0x5B, 0xffeb),
+    key("Modifiers", "rsuper", &["rightsuper", "rwin"], 126, 0x5C, 0xffec),
+    key("Editing", "space", &[], 57, 0x20, 0x20),
+    key("Editing", "enter", &["return"], 28, 0x0D, 0xff0d),
+    key("Editing", "tab", &[], 15, 0x09, 0xff09),
+    key("Editing", "escape", &["esc"], 1, 0x1B, 0xff1b),
+    key("Editing", "backspace", &[], 14, 0x08, 0xff08),
+    key("Editing", "delete", &["del"], 111, 0x2E, 0xffff),
+    key("Editing", "insert", &["ins"], 110, 0x2D, 0xff63),
+    key("Navigation", "left", &[], 105, 0x25, 0xff51),
+    key("Navigation", "up", &[], 103, 0x26, 0xff52),
+    key("Navigation", "right", &[], 106, 0x27, 0xff53),
+    key("Navigation", "down", &[], 108, 0x28, 0xff54),
+    key("Navigation", "home", &[], 102, 0x24, 0xff50),
+    key("Navigation", "end", &[], 107, 0x23, 0xff57),
+    key("Navigation", "pageup", &["pgup"], 104, 0x21, 0xff55),
+    key("Navigation", "pagedown", &["pgdn"], 109, 0x22, 0xff56),
+    key("Punctuation", "minus", &["-"], 12, 0xBD, 0x2d),
+    key("Punctuation", "equal", &["="], 13, 0xBB, 0x3d),
+    key("Punctuation", "leftbracket", &["["], 26, 0xDB, 0x5b),
+    key("Punctuation", "rightbracket", &["]"], 27, 0xDD, 0x5d),
+    key("Punctuation", "backslash", &["\\"], 43, 0xDC, 0x5c),
+    key("Punctuation", "semicolon", &[";"], 39, 0xBA, 0x3b),
+    key("Punctuation", "apostrophe", &["'", "quote"], 40, 0xDE, 0x27),
+    key("Punctuation", "grave", &["`", "backtick"], 41, 0xC0, 0x60),
+    key("Punctuation", "comma", &[","], 51, 0xBC, 0x2c),
+    key("Punctuation", "period", &[".", "dot"], 52, 0xBE, 0x2e),
+    key("Punctuation", "slash", &["/"], 53, 0xBF, 0x2f),
+    key("Punctuation", "oem102", &["102nd"], 86, 0xE2, 0x3c),
+    key("Numpad", "kp0", &["numpad0"], 82, 0x60, 0xffb0),
+    key("Numpad", "kp1", &["numpad1"], 79, 0x61, 0xffb1),
+    key("Numpad", "kp2", &["numpad2"], 80, 0x62, 0xffb2),
+    key("Numpad", "kp3", &["numpad3"], 81, 0x63, 0xffb3),
+    key("Numpad", "kp4", &["numpad4"], 75, 0x64, 0xffb4),
+    key("Numpad", "kp5", &["numpad5"], 76, 0x65, 0xffb5),
+    key("Numpad", "kp6", &["numpad6"], 77, 0x66, 0xffb6),
+    key("Numpad", "kp7", &["numpad7"], 71, 0x67, 0xffb7),
+    key("Numpad", "kp8", &["numpad8"], 72, 0x68, 0xffb8),
+    key("Numpad", "kp9", &["numpad9"], 73, 0x69, 0xffb9),
+    key("Numpad", "kpplus", &["numpadplus", "kpadd"], 78, 0x6B, 0xffab),
+    key("Numpad", "kpminus", &["numpadminus", "kpsubtract"], 74, 0x6D, 0xffad),
+    key("Numpad", "kpmultiply", &["numpadmultiply", "kpasterisk"], 55, 0x6A, 0xffaa),
+    key("Numpad", "kpdivide", &["numpaddivide", "kpslash"], 98, 0x6F, 0xffaf),
+    key("Numpad", "kpdecimal", &["numpaddecimal", "kpdot"], 83, 0x6E, 0xffae),
+    key("Numpad", "kpenter", &["numpadenter"], 96, 0x0D, 0xff8d),
+    key("Locks", "capslock", &["caps"], 58, 0x14, 0xffe5),
+    key("Locks", "numlock", &[], 69, 0x90, 0xff7f),
+    key("Locks", "scrolllock", &["scrlk"], 70, 0x91, 0xff14),
+    key("System", "printscreen", &["print", "prtsc", "sysrq"], 99, 0x2C, 0xff61),
+    key("System", "pause", &["break"], 119, 0x13, 0xff13),
+    key("System", "menu", &["apps", "contextmenu"], 127, 0x5D, 0xff67),
+    key("Media", "volumeup", &["volup"], 115, 0xAF, 0x1008ff13),
+    key("Media", "volumedown", &["voldown"], 114, 0xAE, 0x1008ff11),
+    key("Media", "mute", &["volumemute"], 113, 0xAD, 0x1008ff12),
+    key("Media", "playpause", &["play", "mediaplay"], 164, 0xB3, 0x1008ff14),
+    key("Media", "mediastop", &[], 166, 0xB2, 0x1008ff15),
+    key("Media", "nexttrack", &["medianext"], 163, 0xB0, 0x1008ff17),
+    key("Media", "prevtrack", &["mediaprev", "previoustrack"], 165, 0xB1, 0x1008ff16),
+];
+
+/// Look up a key by canonical name or alias, ignoring case
+pub fn key_def(name: &str) -> Option<&'static KeyDef> {
+    let name = name.to_lowercase();
+    KEYS.iter()
+        .find(|def| def.name == name || def.aliases.contains(&name.as_str()))
+}
+
+/// The character a key name stands for on a US layout, if it is a character
+/// key, e.g. `'/'` for `slash`
+pub fn key_char(key: &str) -> Option<char> {
+    let def = key_def(key)?;
+    std::iter::once(&def.name)
+        .chain(def.aliases)
+        .find_map(|name| {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        })
+}
+
+/// A key given by its code on one platform instead of by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawKey {
+    /// `sc:0x1E`: a PC (set 1) scan code for `sendinput`, `0xE0`-prefixed
+    /// keys written as e.g. `sc:0xE01D`
+    ScanCode(u16),
+    /// `vk:0x41`: a Windows virtual-key code for `sendinput`
+    Vk(u16),
+    /// `evdev:30`: a Linux input event code for `uinput`
+    Evdev(u16),
+    /// `keysym:0xff0d`: an X11 keysym for `xtest` and `xsendevent`
+    Keysym(u32),
+}
+
+impl RawKey {
+    /// Parse `prefix:code`, where the code is decimal or `0x` hex
+    pub fn parse(key: &str) -> Result<Self, String> {
+        let Some((prefix, code)) = key.split_once(':') else {
+            return Err(format!("'{}' is not a raw key code", key));
+        };
+        let prefix = prefix.to_lowercase();
+
+        let value = match code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => code.parse(),
+        }
+        .ok()
+        .filter(|&value| value != 0)
+        .ok_or_else(|| format!("invalid {} code '{}'", prefix, code))?;
+        let narrow = |value: u32| u16::try_from(value).map_err(|_| format!("{} code '{}' is out of range", prefix, code));
+
+        match prefix.as_str() {
+            "sc" => Ok(RawKey::ScanCode(narrow(value)?)),
+            "vk" => Ok(RawKey::Vk(narrow(value)?)),
+            "evdev" => Ok(RawKey::Evdev(narrow(value)?)),
+            "keysym" => Ok(RawKey::Keysym(value)),
+            _ => Err(format!("unknown key code prefix '{}' (expected sc, vk, evdev or keysym)", prefix)),
+        }
+    }
+}
+
+impl fmt::Display for RawKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RawKey::ScanCode(code) => write!(f, "sc:0x{:02x}", code),
+            RawKey::Vk(code) => write!(f, "vk:0x{:02x}", code),
+            RawKey::Evdev(code) => write!(f, "evdev:{}", code),
+            RawKey::Keysym(keysym) => write!(f, "keysym:0x{:x}", keysym),
+        }
+    }
+}
+
+/// The canonical name of `name`, or `None` if it isn't a known key.
+/// Raw key codes are normalized, e.g. `VK:65` becomes `vk:0x41`.
+pub fn canonical_key_name(name: &str) -> Option<String> {
+    if name.contains(':') {
+        return RawKey::parse(name).ok().map(|raw| raw.to_string());
+    }
+    key_def(name).map(|def| def.name.to_string())
+}
+
+/// Keys pressed together, in press order; released in reverse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chord {
+    pub keys: Vec<String>,
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keys.join("+"))
+    }
+}
+
+/// A parsed key expression, see the [module docs](self) for the syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyExpr {
+    /// One key tapped, e.g. `space`
+    Key(String),
+    /// Several keys held together, e.g. `ctrl+s`
+    Chord(Chord),
+    /// Chords sent one after another, e.g. `ctrl+k ctrl+c`
+    Sequence(Vec<Chord>),
+    /// Characters typed one by one, e.g. `'hello'`
+    Text(String),
+}
+
+impl KeyExpr {
+    pub fn parse(input: &str) -> Result<Self, KeyParseError> {
+        Parser { input }.parse()
+    }
+
+    /// What to send, in order. Text is expanded character by character on
+    /// `layout`; characters with no key there become [`Stroke::Unicode`].
+    pub fn strokes(&self, layout: Layout) -> Vec<Stroke> {
+        match self {
+            KeyExpr::Key(key) => vec![Stroke::Chord(Chord { keys: vec![key.clone()] })],
+            KeyExpr::Chord(chord) => vec![Stroke::Chord(chord.clone())],
+            KeyExpr::Sequence(chords) => chords.iter().cloned().map(Stroke::Chord).collect(),
+            KeyExpr::Text(text) => text
+                .chars()
+                .map(|c| layout.char_to_chord(c).map_or(Stroke::Unicode(c), Stroke::Chord))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for KeyExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyExpr::Key(key) => write!(f, "{}", key),
+            KeyExpr::Chord(chord) => write!(f, "{}", chord),
+            KeyExpr::Sequence(chords) => {
+                let chords: Vec<String> = chords.iter().map(Chord::to_string).collect();
+                write!(f, "{}", chords.join(" "))
+            }
+            KeyExpr::Text(text) => write!(f, "'{}'", text),
+        }
+    }
+}
+
+/// One step of sending a [`KeyExpr`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stroke {
+    /// Keys pressed together
+    Chord(Chord),
+    /// A character of text with no key on the layout, left to the backend's
+    /// Unicode input method
+    Unicode(char),
+}
+
+/// Why a key expression was rejected, and where
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyParseError {
+    pub input: String,
+    /// 1-based character column the problem was found at
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for KeyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {} of \"{}\"", self.message, self.column, self.input)
+    }
+}
+
+impl std::error::Error for KeyParseError {}
+
+struct Parser<'a> {
+    input: &'a str,
+}
+
+impl Parser<'_> {
+    fn error(&self, byte_offset: usize, message: String) -> KeyParseError {
+        KeyParseError {
+            input: self.input.to_string(),
+            column: self.input[..byte_offset].chars().count() + 1,
+            message,
+        }
+    }
+
+    fn parse(&self) -> Result<KeyExpr, KeyParseError> {
+        let start = self.input.len() - self.input.trim_start().len();
+        let trimmed = self.input.trim();
+
+        if trimmed.is_empty() {
+            return Err(self.error(0, "key expression is empty".to_string()));
+        }
+
+        if trimmed.len() > 1 && trimmed.starts_with('\'') {
+            return self.parse_text(start, trimmed);
+        }
+
+        // Byte ranges of the chords in `trimmed`. Whitespace separates chords,
+        // except around a '+', so "ctrl + s" is one chord.
+        let mut spans: Vec<(usize, usize)> = Vec::new();
+        for token in trimmed.split_whitespace() {
+            // Tokens borrow from `trimmed`, so their offset falls out of the pointers
+            let begin = token.as_ptr() as usize - trimmed.as_ptr() as usize;
+            let end = begin + token.len();
+            match spans.last_mut() {
+                Some((_, last_end)) if trimmed[..*last_end].ends_with('+') || token.starts_with('+') => *last_end = end,
+                _ => spans.push((begin, end)),
+            }
+        }
+
+        let mut chords = Vec::new();
+        for (begin, end) in spans {
+            chords.push(self.parse_chord(start + begin, &trimmed[begin..end])?);
+        }
+
+        if chords.len() > 1 {
+            return Ok(KeyExpr::Sequence(chords));
+        }
+
+        let mut chord = chords.remove(0);
+        if chord.keys.len() == 1 {
+            Ok(KeyExpr::Key(chord.keys.remove(0)))
+        } else {
+            Ok(KeyExpr::Chord(chord))
+        }
+    }
+
+    fn parse_text(&self, start: usize, trimmed: &str) -> Result<KeyExpr, KeyParseError> {
+        let Some(text) = trimmed[1..].strip_suffix('\'') else {
+            return Err(self.error(start + trimmed.len(), "expected a closing ' after the text".to_string()));
+        };
+
+        if text.is_empty() {
+            return Err(self.error(start + 1, "text is empty".to_string()));
+        }
+
+        Ok(KeyExpr::Text(text.to_string()))
+    }
+
+    fn parse_chord(&self, start: usize, span: &str) -> Result<Chord, KeyParseError> {
+        let mut keys: Vec<String> = Vec::new();
+        let mut part_offset = start;
+
+        for (i, part) in span.split('+').enumerate() {
+            let name = part.trim();
+            let offset = part_offset + (part.len() - part.trim_start().len());
+            part_offset += part.len() + 1;
+
+            if name.is_empty() {
+                let message = if i == 0 { "expected a key before '+'" } else { "expected a key after '+'" };
+                return Err(self.error(offset, message.to_string()));
+            }
+
+            let key = if name.contains(':') {
+                RawKey::parse(name).map_err(|message| self.error(offset, message))?.to_string()
+            } else {
+                canonical_key_name(name).ok_or_else(|| self.error(offset, format!("unknown key '{}'", name)))?
+            };
+
+            if keys.contains(&key) {
+                return Err(self.error(offset, format!("key '{}' appears twice in the same chord", key)));
+            }
+
+            keys.push(key);
+        }
+
+        Ok(Chord { keys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(keys: &[&str]) -> Chord {
+        Chord { keys: keys.iter().map(|k| k.to_string()).collect() }
+    }
+
+    #[test]
+    fn test_parse_forms() {
+        assert_eq!(KeyExpr::parse("Space").unwrap(), KeyExpr::Key("space".to_string()));
+        assert_eq!(KeyExpr::parse(" esc ").unwrap(), KeyExpr::Key("escape".to_string()));
+        assert_eq!(KeyExpr::parse("Control+S").unwrap(), KeyExpr::Chord(chord(&["ctrl", "s"])));
+        assert_eq!(
+            KeyExpr::parse("ctrl+k  ctrl+c").unwrap(),
+            KeyExpr::Sequence(vec![chord(&["ctrl", "k"]), chord(&["ctrl", "c"])])
+        );
+        assert_eq!(KeyExpr::parse("'Hi 2'").unwrap(), KeyExpr::Text("Hi 2".to_string()));
+    }
+
+    #[test]
+    fn test_parse_errors_report_column() {
+        let error = KeyExpr::parse("ctrl++").unwrap_err();
+        assert_eq!((error.column, error.message.as_str()), (6, "expected a key after '+'"));
+
+        let error = KeyExpr::parse("shfit+a").unwrap_err();
+        assert_eq!((error.column, error.message.as_str()), (1, "unknown key 'shfit'"));
+
+        let error = KeyExpr::parse("ctrl+k ctrl+x+ctrl").unwrap_err();
+        assert_eq!(error.column, 15);
+
+        assert_eq!(KeyExpr::parse("+a").unwrap_err().column, 1);
+        assert_eq!(KeyExpr::parse("f25").unwrap_err().column, 1);
+        assert_eq!(KeyExpr::parse("'abc").unwrap_err().column, 5);
+        assert!(KeyExpr::parse("").is_err());
+        assert!(KeyExpr::parse("''").is_err());
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for input in ["a", "ctrl+alt+delete", "ctrl+k ctrl+c", "'Hello World'"] {
+            let expr = KeyExpr::parse(input).unwrap();
+            assert_eq!(expr.to_string(), input);
+            assert_eq!(KeyExpr::parse(&expr.to_string()).unwrap(), expr);
+        }
+    }
+
+    #[test]
+    fn test_text_strokes() {
+        let expr = KeyExpr::parse("'aBé'").unwrap();
+        assert_eq!(expr.strokes(Layout::Us), vec![
+            Stroke::Chord(chord(&["a"])),
+            Stroke::Chord(chord(&["shift", "b"])),
+            Stroke::Unicode('é'),
+        ]);
+        assert_eq!(expr.strokes(Layout::Fr)[2], Stroke::Chord(chord(&["2"])));
+    }
+
+    #[test]
+    fn test_spaces_around_plus() {
+        assert_eq!(KeyExpr::parse("ctrl + s").unwrap(), KeyExpr::Chord(chord(&["ctrl", "s"])));
+        assert_eq!(KeyExpr::parse("ctrl +shift+ s").unwrap(), KeyExpr::Chord(chord(&["ctrl", "shift", "s"])));
+        assert_eq!(
+            KeyExpr::parse("ctrl + k ctrl + c").unwrap(),
+            KeyExpr::Sequence(vec![chord(&["ctrl", "k"]), chord(&["ctrl", "c"])])
+        );
+
+        let error = KeyExpr::parse("ctrl + shfit + s").unwrap_err();
+        assert_eq!((error.column, error.message.as_str()), (8, "unknown key 'shfit'"));
+        let error = KeyExpr::parse("ctrl + ").unwrap_err();
+        assert_eq!((error.column, error.message.as_str()), (7, "expected a key after '+'"));
+    }
+
+    #[test]
+    fn test_punctuation_names() {
+        assert_eq!(KeyExpr::parse("shift+/").unwrap(), KeyExpr::Chord(chord(&["shift", "slash"])));
+        assert_eq!(KeyExpr::parse("'").unwrap(), KeyExpr::Key("apostrophe".to_string()));
+    }
+
+    #[test]
+    fn test_key_table_names_are_unique() {
+        let mut seen = std::collections::HashSet::new();
+        for def in KEYS {
+            for name in std::iter::once(&def.name).chain(def.aliases) {
+                assert!(seen.insert(*name), "'{}' is in the key table twice", name);
+                assert_eq!(name.to_lowercase(), *name);
+                assert!(!name.contains('+') && !name.contains(char::is_whitespace));
+            }
+        }
+    }
+
+    #[test]
+    fn test_extended_key_names() {
+        assert_eq!(canonical_key_name("NumPad5").as_deref(), Some("kp5"));
+        assert_eq!(canonical_key_name("win").as_deref(), Some("super"));
+        assert_eq!(canonical_key_name("f24").as_deref(), Some("f24"));
+        assert_eq!(canonical_key_name("f25"), None);
+        assert_eq!(key_def("prtsc").map(|def| def.vk), Some(0x2C));
+        assert_eq!(
+            KeyExpr::parse("ctrl+volumeup").unwrap(),
+            KeyExpr::Chord(chord(&["ctrl", "volumeup"]))
+        );
+    }
+
+    #[test]
+    fn test_raw_key_codes() {
+        assert_eq!(
+            KeyExpr::parse("ctrl+VK:0x41").unwrap(),
+            KeyExpr::Chord(chord(&["ctrl", "vk:0x41"]))
+        );
+        assert_eq!(KeyExpr::parse("evdev:0x1e").unwrap(), KeyExpr::Key("evdev:30".to_string()));
+        assert_eq!(RawKey::parse("sc:0xE01D"), Ok(RawKey::ScanCode(0xe01d)));
+        assert_eq!(RawKey::parse("keysym:0x1008ff13"), Ok(RawKey::Keysym(0x1008ff13)));
+        assert_eq!(KeyExpr::parse("sc:0x1e").unwrap().to_string(), "sc:0x1e");
+
+        let error = KeyExpr::parse("shift+vk:zz").unwrap_err();
+        assert_eq!((error.column, error.message.as_str()), (7, "invalid vk code 'zz'"));
+        assert!(KeyExpr::parse("usb:4").unwrap_err().message.contains("unknown key code prefix"));
+        assert!(KeyExpr::parse("vk:0x10000").is_err());
+        assert!(KeyExpr::parse("evdev:0").is_err());
+    }
+
+    #[test]
+    fn test_left_right_modifiers() {
+        assert_eq!(canonical_key_name("LShift").as_deref(), Some("lshift"));
+        assert_eq!(canonical_key_name("altgr").as_deref(), Some("ralt"));
+        assert_eq!(key_def("rctrl").map(|def| (def.evdev, def.vk)), Some((97, 0xA3)));
+        assert_eq!(key_def("shift").map(|def| def.vk), Some(0x10));
+    }
+}
@@ -0,0 +1,312 @@
+//! Keyboard layouts: which physical key types which character.
+//!
+//! Keys are named after what they type on a US layout, which is also where
+//! backends like `uinput` get their key codes from. On other layouts the key
+//! at that position types something else, so characters and character key
+//! names are translated through a [`Layout`] before they reach such backends.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::keys::{self, Chord};
+
+/// No character on this level
+const NONE: char = '\0';
+
+/// A key position, named after the US key there, and what it types plain,
+/// with shift and with AltGr. Dead keys are left out.
+type Position = (&'static str, [char; 3]);
+
+const US: &[Position] = &[
+    ("grave", ['`', '~', NONE]), ("1", ['1', '!', NONE]), ("2", ['2', '@', NONE]),
+    ("3", ['3', '#', NONE]), ("4", ['4', '$', NONE]), ("5", ['5', '%', NONE]),
+    ("6", ['6', '^', NONE]), ("7", ['7', '&', NONE]), ("8", ['8', '*', NONE]),
+    ("9", ['9', '(', NONE]), ("0", ['0', ')', NONE]), ("minus", ['-', '_', NONE]),
+    ("equal", ['=', '+', NONE]),
+    ("q", ['q', 'Q', NONE]), ("w", ['w', 'W', NONE]), ("e", ['e', 'E', NONE]),
+    ("r", ['r', 'R', NONE]), ("t", ['t', 'T', NONE]), ("y", ['y', 'Y', NONE]),
+    ("u", ['u', 'U', NONE]), ("i", ['i', 'I', NONE]), ("o", ['o', 'O', NONE]),
+    ("p", ['p', 'P', NONE]), ("leftbracket", ['[', '{', NONE]), ("rightbracket", [']', '}', NONE]),
+    ("backslash", ['\\', '|', NONE]),
+    ("a", ['a', 'A', NONE]), ("s", ['s', 'S', NONE]), ("d", ['d', 'D', NONE]),
+    ("f", ['f', 'F', NONE]), ("g", ['g', 'G', NONE]), ("h", ['h', 'H', NONE]),
+    ("j", ['j', 'J', NONE]), ("k", ['k', 'K', NONE]), ("l", ['l', 'L', NONE]),
+    ("semicolon", [';', ':', NONE]), ("apostrophe", ['\'', '"', NONE]),
+    ("z", ['z', 'Z', NONE]), ("x", ['x', 'X', NONE]), ("c", ['c', 'C', NONE]),
+    ("v", ['v', 'V', NONE]), ("b", ['b', 'B', NONE]), ("n", ['n', 'N', NONE]),
+    ("m", ['m', 'M', NONE]), ("comma", [',', '<', NONE]), ("period", ['.', '>', NONE]),
+    ("slash", ['/', '?', NONE]),
+];
+
+/// German QWERTZ
+const DE: &[Position] = &[
+    ("grave", [NONE, '°', NONE]), ("1", ['1', '!', NONE]), ("2", ['2', '"', '²']),
+    ("3", ['3', '§', '³']), ("4", ['4', '$', NONE]), ("5", ['5', '%', NONE]),
+    ("6", ['6', '&', NONE]), ("7", ['7', '/', '{']), ("8", ['8', '(', '[']),
+    ("9", ['9', ')', ']']), ("0", ['0', '=', '}']), ("minus", ['ß', '?', '\\']),
+    ("q", ['q', 'Q', '@']), ("w", ['w', 'W', NONE]), ("e", ['e', 'E', '€']),
+    ("r", ['r', 'R', NONE]), ("t", ['t', 'T', NONE]), ("y", ['z', 'Z', NONE]),
+    ("u", ['u', 'U', NONE]), ("i", ['i', 'I', NONE]), ("o", ['o', 'O', NONE]),
+    ("p", ['p', 'P', NONE]), ("leftbracket", ['ü', 'Ü', NONE]), ("rightbracket", ['+', '*', '~']),
+    ("backslash", ['#', '\'', NONE]),
+    ("a", ['a', 'A', NONE]), ("s", ['s', 'S', NONE]), ("d", ['d', 'D', NONE]),
+    ("f", ['f', 'F', NONE]), ("g", ['g', 'G', NONE]), ("h", ['h', 'H', NONE]),
+    ("j", ['j', 'J', NONE]), ("k", ['k', 'K', NONE]), ("l", ['l', 'L', NONE]),
+    ("semicolon", ['ö', 'Ö', NONE]), ("apostrophe", ['ä', 'Ä', NONE]),
+    ("oem102", ['<', '>', '|']),
+    ("z", ['y', 'Y', NONE]), ("x", ['x', 'X', NONE]), ("c", ['c', 'C', NONE]),
+    ("v", ['v', 'V', NONE]), ("b", ['b', 'B', NONE]), ("n", ['n', 'N', NONE]),
+    ("m", ['m', 'M', 'µ']), ("comma", [',', ';', NONE]), ("period", ['.', ':', NONE]),
+    ("slash", ['-', '_', NONE]),
+];
+
+/// French AZERTY
+const FR: &[Position] = &[
+    ("grave", ['²', NONE, NONE]), ("1", ['&', '1', NONE]), ("2", ['é', '2', NONE]),
+    ("3", ['"', '3', '#']), ("4", ['\'', '4', '{']), ("5", ['(', '5', '[']),
+    ("6", ['-', '6', '|']), ("7", ['è', '7', NONE]), ("8", ['_', '8', '\\']),
+    ("9", ['ç', '9', '^']), ("0", ['à', '0', '@']), ("minus", [')', '°', ']']),
+    ("equal", ['=', '+', '}']),
+    ("q", ['a', 'A', NONE]), ("w", ['z', 'Z', NONE]), ("e", ['e', 'E', '€']),
+    ("r", ['r', 'R', NONE]), ("t", ['t', 'T', NONE]), ("y", ['y', 'Y', NONE]),
+    ("u", ['u', 'U', NONE]), ("i", ['i', 'I', NONE]), ("o", ['o', 'O', NONE]),
+    ("p", ['p', 'P', NONE]), ("rightbracket", ['$', '£', '¤']),
+    ("backslash", ['*', 'µ', NONE]),
+    ("a", ['q', 'Q', NONE]), ("s", ['s', 'S', NONE]), ("d", ['d', 'D', NONE]),
+    ("f", ['f', 'F', NONE]), ("g", ['g', 'G', NONE]), ("h", ['h', 'H', NONE]),
+    ("j", ['j', 'J', NONE]), ("k", ['k', 'K', NONE]), ("l", ['l', 'L', NONE]),
+    ("semicolon", ['m', 'M', NONE]), ("apostrophe", ['ù', '%', NONE]),
+    ("oem102", ['<', '>', NONE]),
+    ("z", ['w', 'W', NONE]), ("x", ['x', 'X', NONE]), ("c", ['c', 'C', NONE]),
+    ("v", ['v', 'V', NONE]), ("b", ['b', 'B', NONE]), ("n", ['n', 'N', NONE]),
+    ("m", [',', '?', NONE]), ("comma", [';', '.', NONE]), ("period", [':', '/', NONE]),
+    ("slash", ['!', '§', NONE]),
+];
+
+/// Keys held for each level of a [`Position`]
+const LEVEL_MODIFIERS: [&[&str]; 3] = [&[], &["shift"], &["ralt"]];
+
+/// A keyboard layout, selected with `"layout"` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    #[default]
+    Us,
+    De,
+    Fr,
+}
+
+impl Layout {
+    pub const ALL: &'static [Layout] = &[Layout::Us, Layout::De, Layout::Fr];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Layout::Us => "us",
+            Layout::De => "de",
+            Layout::Fr => "fr",
+        }
+    }
+
+    fn positions(&self) -> &'static [Position] {
+        match self {
+            Layout::Us => US,
+            Layout::De => DE,
+            Layout::Fr => FR,
+        }
+    }
+
+    /// Keys that type `c` on this layout, holding shift or AltGr as needed.
+    ///
+    /// Returns `None` for characters with no key on the layout; backends type
+    /// those through their Unicode input method, if they have one.
+    pub fn char_to_chord(&self, c: char) -> Option<Chord> {
+        let special = match c {
+            ' ' => Some("space"),
+            '\n' => Some("enter"),
+            '\t' => Some("tab"),
+            _ => None,
+        };
+        if let Some(key) = special {
+            return Some(Chord { keys: vec![key.to_string()] });
+        }
+
+        // Prefer the plain level, then shift, then AltGr
+        let positions = self.positions();
+        (0..3).find_map(|level| {
+            let (key, _) = positions.iter().find(|(_, chars)| c != NONE && chars[level] == c)?;
+            let mut keys: Vec<String> = LEVEL_MODIFIERS[level].iter().map(|m| m.to_string()).collect();
+            keys.push(key.to_string());
+            Some(Chord { keys })
+        })
+    }
+
+    /// The US-named position of the key labelled with what `key` types on a
+    /// US layout, e.g. `z` is at `y` on a German keyboard.
+    ///
+    /// Only the plain and shift levels count as labels, no modifiers are
+    /// added. Keys that aren't characters, or whose character isn't on a
+    /// label here, keep their position.
+    pub fn physical_key<'a>(&self, key: &'a str) -> &'a str {
+        if *self == Layout::Us {
+            return key;
+        }
+
+        let Some(c) = keys::key_char(key) else {
+            return key;
+        };
+
+        self.positions()
+            .iter()
+            .find(|(_, chars)| chars[0] == c || chars[1] == c)
+            .map_or(key, |(position, _)| position)
+    }
+
+    /// The layout of the current session, from `$XKB_DEFAULT_LAYOUT`, the X
+    /// server's XKB rules or the system keyboard configuration.
+    ///
+    /// Returns the XKB name as is if it isn't a supported layout.
+    pub fn detect() -> Option<Result<Layout, String>> {
+        let name = detect_xkb_layout()?;
+        Some(name.parse().map_err(|_| name))
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Layout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_lowercase();
+        Layout::ALL
+            .iter()
+            .copied()
+            .find(|layout| layout.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Layout::ALL.iter().map(Layout::as_str).collect();
+                anyhow::anyhow!("Unknown layout '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// The first XKB layout name configured for this session
+#[cfg(target_os = "linux")]
+fn detect_xkb_layout() -> Option<String> {
+    let names = std::env::var("XKB_DEFAULT_LAYOUT")
+        .ok()
+        .or_else(x11_rules_layout)
+        .or_else(|| config_file_layout("/etc/default/keyboard"))
+        .or_else(|| config_file_layout("/etc/vconsole.conf"))?;
+
+    // Several layouts are separated by commas, the first is the default
+    let first = names.split(',').next()?.trim();
+    (!first.is_empty()).then(|| first.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn detect_xkb_layout() -> Option<String> {
+    None
+}
+
+/// The layout field of the `_XKB_RULES_NAMES` root window property, which
+/// holds the NUL-separated rules, model, layout, variant and options
+#[cfg(target_os = "linux")]
+fn x11_rules_layout() -> Option<String> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _};
+
+    std::env::var_os("DISPLAY")?;
+    let (conn, screen) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots[screen].root;
+
+    let atom = conn.intern_atom(true, b"_XKB_RULES_NAMES").ok()?.reply().ok()?.atom;
+    if atom == 0 {
+        return None;
+    }
+
+    let reply = conn
+        .get_property(false, root, atom, AtomEnum::STRING, 0, 1024)
+        .ok()?
+        .reply()
+        .ok()?;
+    let layout = reply.value.split(|&b| b == 0).nth(2)?;
+    String::from_utf8(layout.to_vec()).ok()
+}
+
+/// `XKBLAYOUT=...` from a shell-style config file
+#[cfg(target_os = "linux")]
+fn config_file_layout(path: &str) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    content.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("XKBLAYOUT=")?;
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(keys: &[&str]) -> Chord {
+        Chord { keys: keys.iter().map(|k| k.to_string()).collect() }
+    }
+
+    #[test]
+    fn test_us_char_to_chord() {
+        let us = Layout::Us;
+        assert_eq!(us.char_to_chord(','), Some(chord(&["comma"])));
+        assert_eq!(us.char_to_chord('!'), Some(chord(&["shift", "1"])));
+        assert_eq!(us.char_to_chord('"'), Some(chord(&["shift", "apostrophe"])));
+        assert_eq!(us.char_to_chord('\n'), Some(chord(&["enter"])));
+        assert_eq!(us.char_to_chord('é'), None);
+
+        // Every printable ASCII character is on the layout
+        assert!((' '..='~').all(|c| us.char_to_chord(c).is_some()));
+    }
+
+    #[test]
+    fn test_german_and_french_chords() {
+        assert_eq!(Layout::De.char_to_chord('z'), Some(chord(&["y"])));
+        assert_eq!(Layout::De.char_to_chord('@'), Some(chord(&["ralt", "q"])));
+        assert_eq!(Layout::De.char_to_chord('"'), Some(chord(&["shift", "2"])));
+        assert_eq!(Layout::De.char_to_chord('-'), Some(chord(&["slash"])));
+        assert_eq!(Layout::De.char_to_chord('ö'), Some(chord(&["semicolon"])));
+
+        assert_eq!(Layout::Fr.char_to_chord('a'), Some(chord(&["q"])));
+        assert_eq!(Layout::Fr.char_to_chord('1'), Some(chord(&["shift", "1"])));
+        assert_eq!(Layout::Fr.char_to_chord('M'), Some(chord(&["shift", "semicolon"])));
+        assert_eq!(Layout::Fr.char_to_chord('é'), Some(chord(&["2"])));
+    }
+
+    #[test]
+    fn test_physical_key() {
+        assert_eq!(Layout::De.physical_key("z"), "y");
+        assert_eq!(Layout::De.physical_key("y"), "z");
+        assert_eq!(Layout::De.physical_key("minus"), "slash");
+        assert_eq!(Layout::Fr.physical_key("a"), "q");
+        assert_eq!(Layout::Fr.physical_key("m"), "semicolon");
+        assert_eq!(Layout::Fr.physical_key("1"), "1");
+
+        // Not characters, or not on a label: unchanged
+        assert_eq!(Layout::De.physical_key("ctrl"), "ctrl");
+        assert_eq!(Layout::De.physical_key("leftbracket"), "leftbracket");
+        for def in keys::KEYS {
+            assert_eq!(Layout::Us.physical_key(def.name), def.name);
+        }
+    }
+
+    #[test]
+    fn test_layout_parsing() {
+        assert_eq!(" DE ".parse::<Layout>().unwrap(), Layout::De);
+        assert!("dvorak".parse::<Layout>().is_err());
+        for layout in Layout::ALL {
+            assert_eq!(layout.to_string().parse::<Layout>().unwrap(), *layout);
+        }
+    }
+}
@@ -3,10 +3,19 @@
 //! This library provides functionality to send keystrokes to specific processes
 //! with configurable intervals and patterns.
 
+pub mod automation;
+pub mod backend;
 pub mod config;
+pub mod control;
+pub mod hotkey;
 pub mod key_sender;
+pub mod keys;
+pub mod layout;
 pub mod process_finder;
+pub mod timing;
+pub mod watch;
 
+pub use backend::{BackendKind, InputBackend, MockBackend, MouseButton, MouseMotion};
 pub use config::Config;
-pub use key_sender::KeySender;
-pub use process_finder::ProcessFinder;
\ No newline at end of file
+pub use key_sender::{Held, KeySender};
+pub use process_finder::{ProcessFinder, ProcessMatcher, TargetSpec};
\ No newline at end of file
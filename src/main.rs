@@ -1,16 +1,22 @@
 use anyhow::Result;
 use clap::{Arg, Command};
 use colored::Colorize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::time::sleep;
 
-mod config;
-mod key_sender;
-mod process_finder;
-
-use config::Config;
-use key_sender::KeySender;
-use process_finder::ProcessFinder;
+use process_key_sender::automation::{self, OnTargetExit, RunControl, TargetCheck, Targets};
+use process_key_sender::backend::BackendKind;
+use process_key_sender::config::{self, CliOverrides, Config};
+use process_key_sender::control::{self, ControlCommand};
+use process_key_sender::hotkey;
+use process_key_sender::keys;
+use process_key_sender::layout::Layout;
+use process_key_sender::process_finder::{PidSource, PidTarget, ProcessInfo, SelectionPolicy, TargetMode};
+use process_key_sender::timing::{Delay, Distribution, Timing};
+use process_key_sender::watch;
+use process_key_sender::{KeySender, ProcessFinder, ProcessMatcher};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -32,6 +38,21 @@ async fn main() -> Result<()> {
                 .value_name("PROCESS")
                 .help("Target process name (e.g., 'notepad.exe')")
         )
+        .arg(
+            Arg::new("pid")
+                .long("pid")
+                .value_name("PID")
+                .help("Target process ID, instead of searching by name")
+                .value_parser(clap::value_parser!(u32))
+                .conflicts_with_all(["process", "pidfile"])
+        )
+        .arg(
+            Arg::new("pidfile")
+                .long("pidfile")
+                .value_name("PATH")
+                .help("File holding the target process ID, read again when the process restarts")
+                .conflicts_with("process")
+        )
         .arg(
             Arg::new("key")
                 .short('k')
@@ -44,7 +65,7 @@ async fn main() -> Result<()> {
                 .short('i')
                 .long("interval")
                 .value_name("DURATION")
-                .help("Interval between key presses (e.g., '1000ms', '5s')")
+                .help("Interval between key presses (e.g., '1000ms', '1.5s', '900ms..1200ms', '1s±10%')")
                 .default_value("1000ms")
         )
         .arg(
@@ -67,15 +88,98 @@ async fn main() -> Result<()> {
                 .help("Maximum retries to find process")
                 .default_value("10")
         )
+        .arg(
+            Arg::new("backend")
+                .short('b')
+                .long("backend")
+                .value_name("BACKEND")
+                .help("Input backend to send keys with (e.g., 'auto', 'sendinput')")
+                .value_parser(clap::value_parser!(BackendKind))
+        )
+        .arg(
+            Arg::new("layout")
+                .long("layout")
+                .value_name("LAYOUT")
+                .help("Keyboard layout for backends that send physical keys (us, de, fr; default: detected)")
+                .value_parser(clap::value_parser!(Layout))
+        )
+        .arg(
+            Arg::new("select")
+                .long("select")
+                .value_name("POLICY")
+                .help("Which process to pick when several match (lowest_pid, oldest, newest, highest_cpu, highest_memory, fail_if_ambiguous)")
+                .value_parser(clap::value_parser!(SelectionPolicy))
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed for randomized intervals, to repeat a run's timing exactly")
+                .value_parser(clap::value_parser!(u64))
+        )
+        .arg(
+            Arg::new("all")
+                .long("all")
+                .help("Send keys to every matching process, including ones started later")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Find the process and run the schedule, but print keys instead of sending them")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .help("Reload the configuration file whenever it changes")
+                .requires("config")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("control-socket")
+                .long("control-socket")
+                .value_name("PATH")
+                .help("Control socket path (default: $XDG_RUNTIME_DIR/pks.sock)")
+                .global(true)
+        )
+        .subcommand(
+            Command::new("ctl")
+                .about("Control a running instance through its control socket")
+                .arg(
+                    Arg::new("command")
+                        .value_name("COMMAND")
+                        .help("Command to send")
+                        .required(true)
+                        .value_parser(ControlCommand::NAMES.to_vec())
+                )
+        )
+        .subcommand(
+            Command::new("keys")
+                .about("List every key name and alias accepted in key expressions")
+        )
         .get_matches();
 
+    match matches.subcommand() {
+        Some(("ctl", ctl_matches)) => return run_ctl(ctl_matches).await,
+        Some(("keys", _)) => {
+            print_keys();
+            return Ok(());
+        }
+        _ => {}
+    }
+
     // Handle config file loading or CLI argument parsing
-    let config = if let Some(config_file) = matches.get_one::<String>("config") {
+    let mut config = if let Some(config_file) = matches.get_one::<String>("config") {
         load_config_file(config_file)?
     } else {
         create_config_from_args(&matches)?
     };
 
+    // Command line settings override the config file
+    let overrides = cli_overrides(&matches);
+    overrides.apply(&mut config);
+
     // Save config if requested
     if let Some(save_path) = matches.get_one::<String>("save-config") {
         config.save_to_file(save_path)?;
@@ -83,18 +187,105 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    // Initialize the input backend (keys are validated against it)
+    let key_sender = KeySender::from_config(&config)?;
+
     // Validate configuration
-    validate_config(&config)?;
+    check_config(&config, &key_sender)?;
 
     // Print startup information
-    print_startup_info(&config);
+    print_startup_info(&config, &key_sender);
 
     // Initialize components
     let mut process_finder = ProcessFinder::new();
-    let key_sender = KeySender::new()?;
 
     // Main execution loop
-    run_automation(config, &mut process_finder, &key_sender).await
+    let config_file = matches.get_one::<String>("config").cloned();
+    let watch = matches.get_flag("watch");
+    let result = run_automation(config, config_file, &overrides, watch, &mut process_finder, &key_sender).await;
+
+    if let Some(lost) = result.as_ref().err().and_then(|e| e.downcast_ref::<TargetLost>()) {
+        eprintln!("{} {}", "✗".red(), lost);
+        std::process::exit(lost.code.into());
+    }
+    result
+}
+
+/// Why `pks` stopped once its target process was gone for good
+#[derive(Debug)]
+struct TargetLost {
+    code: u8,
+}
+
+impl std::fmt::Display for TargetLost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Target process exited (exit code {})", self.code)
+    }
+}
+
+impl std::error::Error for TargetLost {}
+
+async fn run_ctl(matches: &clap::ArgMatches) -> Result<()> {
+    let command = ControlCommand::from_name(matches.get_one::<String>("command").unwrap())?;
+    let socket = matches.get_one::<String>("control-socket")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(control::default_socket_path);
+
+    let response = control::send_command(&socket, command).await?;
+
+    if !response.ok {
+        anyhow::bail!("{}", response.message.unwrap_or_else(|| "command failed".to_string()));
+    }
+
+    if let Some(status) = response.status {
+        let state = if status.paused {
+            "PAUSED".yellow()
+        } else if status.target_lost {
+            "WAITING FOR TARGET".yellow()
+        } else {
+            "RUNNING".green()
+        };
+        println!("{} State: {}", "📊".blue(), state);
+        if status.target_pids.len() > 1 {
+            let pids: Vec<String> = status.target_pids.iter().map(ToString::to_string).collect();
+            println!("{} Target PIDs: {}", "🎯".blue(), pids.join(", ").yellow());
+        } else {
+            println!("{} Target PID: {}", "🎯".blue(), status.target_pid.to_string().yellow());
+        }
+        println!("{} Iterations: {}", "🔁".blue(), status.iterations.to_string().yellow());
+        println!("{} Keys Sent: {}", "⌨".blue(), status.keys_sent.to_string().yellow());
+        println!("{} Errors: {}", "✗".red(), status.errors.to_string().yellow());
+    } else if let Some(message) = response.message {
+        println!("{} {}", "✓".green(), message);
+    }
+
+    Ok(())
+}
+
+fn print_keys() {
+    let mut group = "";
+
+    for def in keys::KEYS {
+        if def.group != group {
+            if !group.is_empty() {
+                println!();
+            }
+            group = def.group;
+            println!("{} {}", "⌨".blue(), group.bold());
+        }
+
+        if def.aliases.is_empty() {
+            println!("  {}", def.name.cyan());
+        } else {
+            println!("  {} {}", def.name.cyan(), format!("({})", def.aliases.join(", ")).dimmed());
+        }
+    }
+
+    println!();
+    println!("{} {}", "⌨".blue(), "Raw key codes".bold());
+    println!("  {} {}", "sc:0x1E vk:0x41".cyan(), "(sendinput)".dimmed());
+    println!("  {} {}", "evdev:30".cyan(), "(uinput)".dimmed());
+    println!("  {} {}", "keysym:0xff0d".cyan(), "(xtest, xsendevent)".dimmed());
 }
 
 fn load_config_file(config_file: &str) -> Result<Config> {
@@ -112,17 +303,34 @@ fn load_config_file(config_file: &str) -> Result<Config> {
     }
 }
 
+fn cli_overrides(matches: &clap::ArgMatches) -> CliOverrides {
+    CliOverrides {
+        pid: matches.get_one::<u32>("pid").copied(),
+        pidfile: matches.get_one::<String>("pidfile").cloned(),
+        backend: matches.get_one::<BackendKind>("backend").copied(),
+        layout: matches.get_one::<Layout>("layout").copied(),
+        select: matches.get_one::<SelectionPolicy>("select").copied(),
+        seed: matches.get_one::<u64>("seed").copied(),
+        all: matches.get_flag("all"),
+        dry_run: matches.get_flag("dry-run"),
+        control_socket: matches.get_one::<String>("control-socket").cloned(),
+    }
+}
+
 fn create_config_from_args(matches: &clap::ArgMatches) -> Result<Config> {
-    let process_name = matches.get_one::<String>("process")
-        .ok_or_else(|| anyhow::anyhow!("Process name is required. Use --process or --config."))?
-        .clone();
+    let process_name = match matches.get_one::<String>("process") {
+        Some(process_name) => process_name.clone(),
+        // Filled in from --pid or --pidfile by the caller
+        None if matches.contains_id("pid") || matches.contains_id("pidfile") => String::new(),
+        None => anyhow::bail!("Process name is required. Use --process, --pid, --pidfile or --config."),
+    };
 
     let key = matches.get_one::<String>("key")
         .ok_or_else(|| anyhow::anyhow!("Key is required. Use --key or --config."))?
         .clone();
 
     let interval_str = matches.get_one::<String>("interval").unwrap();
-    let interval = parse_duration(interval_str)?;
+    let interval = Delay::parse(interval_str)?;
 
     let max_retries: u32 = matches.get_one::<String>("max-retries")
         .unwrap()
@@ -132,8 +340,18 @@ fn create_config_from_args(matches: &clap::ArgMatches) -> Result<Config> {
 
     Ok(Config {
         process_name,
+        target: None,
+        pid: None,
+        pidfile: None,
+        select: SelectionPolicy::default(),
+        target_mode: TargetMode::default(),
+        broadcast: Default::default(),
+        rescan_interval: Delay::fixed(Duration::from_secs(2)),
+        on_target_exit: OnTargetExit::default(),
+        target_exit_code: 3,
         key_sequence: vec![config::KeyAction {
-            key,
+            action: config::Action::Key(key),
+            char_delay: None,
             interval_after: interval,
         }],
         independent_keys: vec![],
@@ -143,99 +361,70 @@ fn create_config_from_args(matches: &clap::ArgMatches) -> Result<Config> {
         loop_sequence: true,
         repeat_count: 0,
         restore_focus: true,
+        backend: BackendKind::Auto,
+        mock_output: None,
+        dry_run: false,
+        control_socket: None,
+        layout: None,
+        seed: None,
+        distribution: Distribution::default(),
+        jitter: 0,
+        hold_time: None,
     })
 }
 
-fn parse_duration(s: &str) -> Result<Duration> {
-    let s = s.trim().to_lowercase();
-
-    if s.ends_with("ms") {
-        let num_str = &s[..s.len() - 2];
-        let ms: u64 = num_str.parse()?;
-        Ok(Duration::from_millis(ms))
-    } else if s.ends_with('s') {
-        let num_str = &s[..s.len() - 1];
-        let secs: u64 = num_str.parse()?;
-        Ok(Duration::from_secs(secs))
-    } else if s.ends_with('m') {
-        let num_str = &s[..s.len() - 1];
-        let mins: u64 = num_str.parse()?;
-        Ok(Duration::from_secs(mins * 60))
-    } else {
-        // Default to milliseconds if no suffix
-        let ms: u64 = s.parse()?;
-        Ok(Duration::from_millis(ms))
-    }
-}
-
-fn validate_config(config: &Config) -> Result<()> {
-    if config.process_name.is_empty() {
-        anyhow::bail!("Process name cannot be empty");
-    }
-
-    if config.key_sequence.is_empty() && config.independent_keys.is_empty() {
-        anyhow::bail!("At least one key sequence or independent key must be configured");
-    }
+/// Print the warnings for `config` and fail with its errors, if any
+fn check_config(config: &Config, key_sender: &KeySender) -> Result<()> {
+    let report = config.check_with(key_sender);
 
-    if !config.key_sequence.is_empty() && !config.independent_keys.is_empty() {
-        anyhow::bail!("Cannot use both key_sequence and independent_keys simultaneously. Choose one mode.");
+    for warning in &report.warnings {
+        println!("{} Warning: {}", "⚠".yellow(), warning);
     }
 
-    if config.max_retries == 0 {
-        anyhow::bail!("max_retries must be greater than 0");
-    }
-
-    // Validate all keys
-    let key_sender = KeySender::new()?;
-
-    for key_action in &config.key_sequence {
-        validate_key(&key_sender, &key_action.key)?;
-        if key_action.interval_after < Duration::from_millis(50) {
-            println!("{} Warning: Very short interval ({}ms) for key '{}' may cause issues",
-                     "⚠".yellow(),
-                     key_action.interval_after.as_millis(),
-                     key_action.key
-            );
-        }
-    }
-
-    for independent_key in &config.independent_keys {
-        validate_key(&key_sender, &independent_key.key)?;
-        if independent_key.interval < Duration::from_millis(50) {
-            println!("{} Warning: Very short interval ({}ms) for key '{}' may cause issues",
-                     "⚠".yellow(),
-                     independent_key.interval.as_millis(),
-                     independent_key.key
-            );
-        }
-    }
-
-    Ok(())
+    report.into_result()
 }
 
-fn validate_key(key_sender: &KeySender, key: &str) -> Result<()> {
-    // Try to parse the key to ensure it's valid
-    key_sender.parse_key_for_validation(key)
-        .map_err(|e| anyhow::anyhow!("Invalid key '{}': {}", key, e))?;
-    Ok(())
-}
-
-fn print_startup_info(config: &Config) {
+fn print_startup_info(config: &Config, key_sender: &KeySender) {
     println!("\n{}", "🚀 Process Key Sender v0.1.1".bold().cyan());
     println!("{}", "═".repeat(40).cyan());
 
-    println!("{} Target Process: {}", "🎯".blue(), config.process_name.yellow());
+    println!("{} Target Process: {}", "🎯".blue(), describe_target(config).yellow());
+    if config.target_mode == TargetMode::All {
+        println!("{} Target Mode: {}", "📡".blue(), format!("all ({}, rescan every {})", config.broadcast, config.rescan_interval).yellow());
+    } else if config.select != SelectionPolicy::default() {
+        println!("{} Process Selection: {}", "🧭".blue(), config.select.to_string().yellow());
+    }
     println!("{} Max Retries: {}", "🔄".blue(), config.max_retries.to_string().yellow());
+    if config.on_target_exit == OnTargetExit::Exit {
+        println!("{} On Target Exit: {}", "🚪".blue(), format!("exit with code {}", config.target_exit_code).yellow());
+    }
+    println!("{} Input Backend: {}", "🖥".blue(), key_sender.backend_name().yellow());
+    let layout = config.layout.map_or_else(|| "auto".to_string(), |layout| layout.to_string());
+    println!("{} Keyboard Layout: {}", "🌐".blue(), layout.yellow());
+    if config.jitter > 0 {
+        println!("{} Jitter: {}", "🎲".blue(), format!("{}%", config.jitter).yellow());
+    }
+    if let Some(seed) = config.seed {
+        println!("{} Timing Seed: {}", "🌱".blue(), seed.to_string().yellow());
+    }
     println!("{} Pause Hotkey: {}", "⏸".blue(), config.pause_hotkey.yellow());
     println!("{} Verbose Mode: {}", "📝".blue(), if config.verbose { "ON".green() } else { "OFF".red() });
+    if config.dry_run {
+        println!(
+            "{} Dry Run: {} (keys are printed, not sent; checked against {})",
+            "🧪".blue(),
+            "ON".green(),
+            config.backend.resolve().to_string().yellow()
+        );
+    }
 
     if !config.key_sequence.is_empty() {
         println!("\n{} Key Sequence Mode:", "⌨".blue());
         for (i, key_action) in config.key_sequence.iter().enumerate() {
-            println!("  {}. {} (wait {}ms)",
+            println!("  {}. {} (wait {})",
                      i + 1,
-                     key_action.key.cyan(),
-                     key_action.interval_after.as_millis().to_string().yellow()
+                     key_action.action.to_string().cyan(),
+                     key_action.interval_after.to_string().yellow()
             );
         }
         println!("  {} Loop: {}", "🔁".blue(), if config.loop_sequence { "YES".green() } else { "NO".red() });
@@ -247,9 +436,9 @@ fn print_startup_info(config: &Config) {
     if !config.independent_keys.is_empty() {
         println!("\n{} Independent Keys Mode:", "⌨".blue());
         for independent_key in &config.independent_keys {
-            println!("  {} every {}ms",
+            println!("  {} every {}",
                      independent_key.key.cyan(),
-                     independent_key.interval.as_millis().to_string().yellow()
+                     independent_key.interval.to_string().yellow()
             );
         }
     }
@@ -260,141 +449,387 @@ fn print_startup_info(config: &Config) {
 
 async fn run_automation(
     config: Config,
+    config_file: Option<String>,
+    overrides: &CliOverrides,
+    watch: bool,
     process_finder: &mut ProcessFinder,
     key_sender: &KeySender
 ) -> Result<()> {
     // Find target process
-    let window_id = find_target_process(&config, process_finder).await?;
+    let window_ids = find_target_processes(&config, process_finder).await?;
 
     println!("{} Process found! Starting automation...", "✓".green());
 
-    // Run appropriate automation mode
-    if !config.independent_keys.is_empty() {
-        run_independent_keys(&config, key_sender, window_id).await
+    for &window_id in &window_ids {
+        print_delivery_report(key_sender, window_id);
+    }
+
+    let control = RunControl::new();
+    let mut targets = Targets::new(window_ids);
+    record_targets(&control, &targets);
+
+    if let Some(source) = config.pid_source() {
+        targets = targets.with_check(pid_check(source, control.clone()));
+    }
+
+    let target_lost = if config.target_mode == TargetMode::All {
+        spawn_target_rescan(&config, targets.clone(), control.clone())?;
+        None
     } else {
-        run_key_sequence(&config, key_sender, window_id).await
+        Some(spawn_target_monitor(&config, targets.clone(), control.clone(), key_sender.clone()))
+    };
+
+    // Stop on Ctrl+C
+    let ctrl_c_shutdown = control.shutdown.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            println!("\n{} Shutting down...", "🛑".yellow());
+            ctrl_c_shutdown.trigger();
+        }
+    });
+
+    // SIGTERM, SIGUSR1 and SIGHUP
+    #[cfg(unix)]
+    if let Err(e) = control::spawn_signal_handlers(&control) {
+        eprintln!("{} Signal handling unavailable: {}", "⚠".yellow(), e);
     }
-}
 
-async fn find_target_process(config: &Config, process_finder: &mut ProcessFinder) -> Result<u64> {
-    println!("{} Searching for process: {}", "🔍".blue(), config.process_name.yellow());
+    // Kept alive until the automation ends, which removes the socket
+    let _control_server = start_control_server(&config, &control).await;
 
-    for attempt in 1..=config.max_retries {
-        if config.verbose {
-            println!("  Attempt {}/{}", attempt, config.max_retries);
+    // The hotkey itself was validated with the config; this only fails when
+    // there is no keyboard or display to listen on
+    match hotkey::spawn_pause_listener(&config.pause_hotkey, control.pause.clone()) {
+        Ok(method) => {
+            println!("{} Pause hotkey {} active ({})", "⏸".blue(), config.pause_hotkey.cyan(), method);
+        }
+        Err(e) => {
+            eprintln!("{} Pause hotkey unavailable: {}", "⚠".yellow(), e);
         }
+    }
+
+    if let (true, Some(config_file)) = (watch, &config_file) {
+        watch::spawn_config_watcher(config_file.as_ref(), control.clone(), watch::DEFAULT_POLL_INTERVAL);
+        println!("{} Watching {} for changes", "👀".blue(), config_file.cyan());
+    }
+
+    // Run appropriate automation mode, picking up reloads of the config file
+    let started = config.clone();
+    let reload = || -> Result<Config> {
+        let Some(config_file) = &config_file else {
+            anyhow::bail!("pks was started without --config, there is nothing to reload");
+        };
+        let mut new_config = Config::from_file(config_file)?;
+        overrides.apply(&mut new_config);
+        check_config(&new_config, key_sender)?;
+        started.check_reload(&new_config)?;
+        Ok(new_config)
+    };
+
+    let exit_code = config.target_exit_code;
+    automation::run_with_reload(config, key_sender, &targets, &control, reload).await?;
+
+    if target_lost.is_some_and(|lost| lost.load(Ordering::Relaxed)) {
+        return Err(TargetLost { code: exit_code }.into());
+    }
+    Ok(())
+}
+
+/// Show the current targets in `pks ctl status`
+fn record_targets(control: &RunControl, targets: &Targets) {
+    let window_ids = targets.get();
+    let first = window_ids.first().copied().unwrap_or(0);
+    control.stats.target_pid.store(first, Ordering::Relaxed);
+    *control.stats.target_pids.lock().unwrap() = window_ids;
+}
+
+/// Check every `rescan_interval` that the target still runs. When it has
+/// exited the loops are held and, depending on `on_target_exit`, the target is
+/// searched for again like at startup or the automation stops.
+///
+/// The returned flag is set if the automation stopped because the target was lost.
+fn spawn_target_monitor(config: &Config, targets: Targets, control: RunControl, key_sender: KeySender) -> Arc<AtomicBool> {
+    let config = config.clone();
+    let timing = Timing::from_config(&config);
+    let lost = Arc::new(AtomicBool::new(false));
+    let gave_up = lost.clone();
+
+    tokio::spawn(async move {
+        let mut process_finder = ProcessFinder::new();
+
+        loop {
+            tokio::select! {
+                _ = sleep(timing.sample(config.rescan_interval)) => {}
+                _ = control.shutdown.wait() => break,
+            }
 
-        match process_finder.find_process_window(&config.process_name) {
-            Ok(Some(window_id)) => {
-                println!("{} Found process window (ID: {})", "✓".green(), window_id.to_string().cyan());
-                return Ok(window_id);
+            let window_ids = targets.get();
+            if window_ids.iter().any(|&window_id| process_finder.is_alive(window_id as u32)) {
+                continue;
             }
-            Ok(None) => {
-                if config.verbose {
-                    println!("  Process not found, retrying...");
+
+            println!("\n{} Target process exited, holding keys", "⚠".yellow());
+            control.target_lost.set_paused(true);
+            // Keys held down through uinput would otherwise stay down
+            for &window_id in &window_ids {
+                if let Err(e) = key_sender.release_held_keys_on(window_id) {
+                    eprintln!("{} Failed to release held keys: {}", "✗".red(), e);
                 }
             }
-            Err(e) => {
-                eprintln!("{} Error searching for process: {}", "✗".red(), e);
+
+            if config.on_target_exit == OnTargetExit::Exit {
+                gave_up.store(true, Ordering::Relaxed);
+                control.shutdown.trigger();
+                break;
             }
-        }
 
-        if attempt < config.max_retries {
-            sleep(Duration::from_millis(1000)).await;
+            let found = tokio::select! {
+                found = find_target_processes(&config, &mut process_finder) => found,
+                _ = control.shutdown.wait() => break,
+            };
+            match found {
+                Ok(window_ids) => {
+                    targets.set(window_ids);
+                    record_targets(&control, &targets);
+                    println!("{} Target process is back, resuming", "▶".green());
+                    control.target_lost.set_paused(false);
+                }
+                Err(e) => {
+                    eprintln!("{} {}", "✗".red(), e);
+                    gave_up.store(true, Ordering::Relaxed);
+                    control.shutdown.trigger();
+                    break;
+                }
+            }
         }
-    }
+    });
 
-    anyhow::bail!("Could not find process '{}' after {} attempts", config.process_name, config.max_retries);
+    lost
 }
 
-async fn run_independent_keys(config: &Config, key_sender: &KeySender, window_id: u64) -> Result<()> {
-    println!("{} Starting independent keys automation...", "🚀".green());
-
-    let mut handles = Vec::new();
+/// Keep `targets` in step with the running processes that match the config,
+/// every `rescan_interval` until the automation stops
+fn spawn_target_rescan(config: &Config, targets: Targets, control: RunControl) -> Result<()> {
+    let matcher = ProcessMatcher::new(&config.target())?;
+    let rescan_interval = config.rescan_interval;
+    let timing = Timing::from_config(config);
+    let mut process_finder = ProcessFinder::new();
 
-    for independent_key in &config.independent_keys {
-        let key = independent_key.key.clone();
-        let interval = independent_key.interval;
-        let sender = key_sender.clone();
-        let wid = window_id;
-        let verbose = config.verbose;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = sleep(timing.sample(rescan_interval)) => {}
+                _ = control.shutdown.wait() => break,
+            }
 
-        let handle = tokio::spawn(async move {
-            loop {
-                match sender.send_key_to_window(wid, &key) {
-                    Ok(_) => {
-                        if verbose {
-                            println!("✓ Sent key: {}", key.cyan());
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("{} Error sending key '{}': {}", "✗".red(), key, e);
-                    }
+            let found = match process_finder.find_matches(&matcher) {
+                Ok(found) => found,
+                Err(e) => {
+                    eprintln!("{} Error searching for process: {}", "✗".red(), e);
+                    continue;
                 }
+            };
 
-                sleep(interval).await;
+            let before = targets.get();
+            let window_ids: Vec<u64> = found.iter().map(|process| process.pid as u64).collect();
+            if !targets.set(window_ids.clone()) {
+                continue;
             }
-        });
-
-        handles.push(handle);
-    }
-
-    // Wait for Ctrl+C
-    tokio::signal::ctrl_c().await?;
-    println!("\n{} Shutting down...", "🛑".yellow());
 
-    // Cancel all tasks
-    for handle in handles {
-        handle.abort();
-    }
+            for window_id in window_ids.iter().filter(|id| !before.contains(id)) {
+                println!("{} New target process (ID: {})", "➕".green(), window_id.to_string().cyan());
+            }
+            for window_id in before.iter().filter(|id| !window_ids.contains(id)) {
+                println!("{} Target process exited (ID: {})", "➖".yellow(), window_id.to_string().cyan());
+            }
+            record_targets(&control, &targets);
+        }
+    });
 
     Ok(())
 }
 
-async fn run_key_sequence(config: &Config, key_sender: &KeySender, window_id: u64) -> Result<()> {
-    println!("{} Starting key sequence automation...", "🚀".green());
+#[cfg(unix)]
+async fn start_control_server(config: &Config, control: &RunControl) -> Option<control::ControlServer> {
+    let path = config.control_socket.as_ref()
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(control::default_socket_path);
 
-    let mut iteration = 0u32;
+    match control::ControlServer::start(&path, control.clone()).await {
+        Ok(server) => {
+            println!("{} Control socket: {}", "🔌".blue(), server.path().display().to_string().cyan());
+            Some(server)
+        }
+        Err(e) => {
+            eprintln!("{} Control socket unavailable: {}", "⚠".yellow(), e);
+            None
+        }
+    }
+}
 
-    loop {
-        iteration += 1;
+#[cfg(not(unix))]
+async fn start_control_server(_config: &Config, _control: &RunControl) -> Option<()> {
+    None
+}
 
-        if config.verbose {
-            println!("--- Sequence iteration {} ---", iteration.to_string().cyan());
+fn print_delivery_report(key_sender: &KeySender, window_id: u64) {
+    match key_sender.delivery_report(window_id) {
+        Ok(reports) => {
+            for report in reports {
+                if report.likely_works {
+                    println!("{} Window {:#x}: {}", "✓".green(), report.window, report.detail);
+                } else {
+                    println!("{} Window {:#x}: {} (keys may not arrive)", "⚠".yellow(), report.window, report.detail);
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("{} Could not check key delivery: {}", "⚠".yellow(), e);
         }
+    }
+}
 
-        for (i, key_action) in config.key_sequence.iter().enumerate() {
-            // Check if we should stop
-            if let Ok(_) = tokio::time::timeout(Duration::from_millis(1), tokio::signal::ctrl_c()).await {
-                println!("\n{} Shutting down...", "🛑".yellow());
-                return Ok(());
-            }
+/// The plain process name, the filters of a structured `target`, or the PID source
+fn describe_target(config: &Config) -> String {
+    match (&config.target, config.pid_source()) {
+        (_, Some(source)) => source.to_string(),
+        (Some(target), None) => target.to_string(),
+        (None, None) => config.process_name.clone(),
+    }
+}
+
+/// List every matching process, marking the ones keys will go to
+fn print_candidates(candidates: &[ProcessInfo], picked: &[u64], heading: &str) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+
+    println!("  {} processes match, {}:", candidates.len(), heading);
+    for process in candidates {
+        let marker = if picked.contains(&(process.pid as u64)) { "→" } else { " " };
+        let uptime = Duration::from_secs(now.saturating_sub(process.start_time));
+        println!(
+            "  {} {:>7}  {:<16} up {:<9} cpu {:>5.1}%  mem {:>6} MiB  {}",
+            marker.green(),
+            process.pid,
+            process.name,
+            config::duration_to_string(uptime),
+            process.cpu_usage,
+            process.memory / (1024 * 1024),
+            process.cmd.join(" ").dimmed()
+        );
+    }
+}
 
-            match key_sender.send_key_to_window(window_id, &key_action.key) {
-                Ok(_) => {
-                    if config.verbose {
-                        println!("  {}. ✓ Sent key: {}", i + 1, key_action.key.cyan());
-                    }
+/// Find the process to send keys to, or every matching one in `all` mode,
+/// and return their window IDs
+async fn find_target_processes(config: &Config, process_finder: &mut ProcessFinder) -> Result<Vec<u64>> {
+    println!("{} Searching for process: {}", "🔍".blue(), describe_target(config).yellow());
+
+    let mut attempts = Attempts::new(config);
+    let window_ids = match config.pid_source() {
+        Some(source) => {
+            let mut pid_target = PidTarget::new(source);
+            loop {
+                attempts.next().await?;
+                if let Some(pid) = report_search_error(pid_target.find()).flatten() {
+                    break vec![pid as u64];
                 }
-                Err(e) => {
-                    eprintln!("  {}. {} Error sending key '{}': {}", i + 1, "✗".red(), key_action.key, e);
+            }
+        }
+        None => {
+            let matcher = ProcessMatcher::new(&config.target())?;
+            loop {
+                attempts.next().await?;
+                let candidates = report_search_error(process_finder.find_candidates_async(&matcher, config.select).await);
+                let Some(candidates) = candidates.filter(|candidates| !candidates.is_empty()) else {
+                    continue;
+                };
+
+                // For now the PID doubles as the window ID
+                let (window_ids, heading) = match config.target_mode {
+                    TargetMode::All => (
+                        candidates.iter().map(|process| process.pid as u64).collect(),
+                        "sending to all".to_string(),
+                    ),
+                    // An ambiguous match won't resolve itself by retrying
+                    TargetMode::Single => (
+                        config.select.select(&candidates)?.map(|process| process.pid as u64).into_iter().collect::<Vec<_>>(),
+                        format!("picking by {}", config.select),
+                    ),
+                };
+                if config.verbose && candidates.len() > 1 {
+                    print_candidates(&candidates, &window_ids, &heading);
                 }
+                break window_ids;
             }
-
-            sleep(key_action.interval_after).await;
         }
+    };
+
+    for window_id in &window_ids {
+        println!("{} Found process window (ID: {})", "✓".green(), window_id.to_string().cyan());
+    }
+    Ok(window_ids)
+}
 
-        // Check repeat count
-        if config.repeat_count > 0 && iteration >= config.repeat_count {
-            println!("{} Completed {} iterations", "✓".green(), config.repeat_count.to_string().cyan());
-            break;
+/// Up to `max_retries` searches for the target, a second apart
+struct Attempts<'a> {
+    config: &'a Config,
+    made: u32,
+}
+
+impl<'a> Attempts<'a> {
+    fn new(config: &'a Config) -> Self {
+        Self { config, made: 0 }
+    }
+
+    /// Wait until the next search is due, failing once every attempt has been used
+    async fn next(&mut self) -> Result<()> {
+        let config = self.config;
+        if self.made > 0 {
+            if config.verbose {
+                println!("  Process not found, retrying...");
+            }
+            if self.made >= config.max_retries {
+                anyhow::bail!("Could not find process '{}' after {} attempts", describe_target(config), config.max_retries);
+            }
+            sleep(Duration::from_millis(1000)).await;
         }
 
-        // Check if we should loop
-        if !config.loop_sequence {
-            break;
+        self.made += 1;
+        if config.verbose {
+            println!("  Attempt {}/{}", self.made, config.max_retries);
         }
+        Ok(())
     }
+}
 
-    Ok(())
-}
\ No newline at end of file
+/// Print a failed search, which is retried like one that found nothing
+fn report_search_error<T>(result: Result<T>) -> Option<T> {
+    result
+        .map_err(|e| eprintln!("{} Error searching for process: {}", "✗".red(), e))
+        .ok()
+}
+
+/// Check before every send that the `pid` or `pidfile` target still runs,
+/// following the pidfile to the new PID when the process restarts
+fn pid_check(source: PidSource, control: RunControl) -> TargetCheck {
+    let pid_target = Mutex::new(PidTarget::new(source));
+    let running = AtomicBool::new(true);
+
+    Arc::new(move |window_id| {
+        let checked = pid_target.lock().unwrap().check(window_id as u32).map(u64::from);
+        match checked {
+            Some(new_id) if new_id != window_id => {
+                println!("{} Target process restarted (ID: {})", "🔁".green(), new_id.to_string().cyan());
+                control.stats.target_pid.store(new_id, Ordering::Relaxed);
+            }
+            None if running.load(Ordering::Relaxed) => {
+                eprintln!("{} Target process {} is not running, skipping keys", "⚠".yellow(), window_id);
+            }
+            _ => {}
+        }
+        running.store(checked.is_some(), Ordering::Relaxed);
+        checked
+    })
+}
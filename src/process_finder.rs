@@ -1,5 +1,395 @@
 use anyhow::Result;
-use sysinfo::{System};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, System, UpdateKind, Users, MINIMUM_CPU_UPDATE_INTERVAL};
+
+/// Which process to send keys to, as written in the `target` object of the config.
+///
+/// Every filter that is set has to match. Name filters are tried against both
+/// the process name and the executable's file name, since Linux cuts process
+/// names off after 15 characters.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetSpec {
+    /// Exact process name, case-insensitive, with or without `.exe`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Process name glob with `*` and `?`, case-insensitive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_glob: Option<String>,
+    /// Regular expression searched for in the process name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_regex: Option<String>,
+    /// Full path of the executable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe: Option<String>,
+    /// Text the command line, arguments joined by spaces, has to contain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cmdline: Option<String>,
+    /// Regular expression searched for in the command line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cmdline_regex: Option<String>,
+    /// Numeric user ID the process runs as (Unix only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    /// Name of the user the process runs as
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+impl TargetSpec {
+    /// Match processes named exactly `name`, like a plain `process_name`
+    pub fn name(name: &str) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Lists the filters that are set, e.g. `name "java", cmdline contains "minecraft"`
+impl fmt::Display for TargetSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        let mut add = |label: &str, value: &Option<String>| {
+            if let Some(value) = value {
+                parts.push(format!("{} {:?}", label, value));
+            }
+        };
+        add("name", &self.name);
+        add("name glob", &self.name_glob);
+        add("name regex", &self.name_regex);
+        add("exe", &self.exe);
+        add("cmdline contains", &self.cmdline);
+        add("cmdline regex", &self.cmdline_regex);
+        add("user", &self.user);
+        if let Some(uid) = self.uid {
+            parts.push(format!("uid {}", uid));
+        }
+
+        if parts.is_empty() {
+            f.write_str("any process")
+        } else {
+            f.write_str(&parts.join(", "))
+        }
+    }
+}
+
+/// What a [`ProcessMatcher`] gets to see of a running process
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub exe: Option<PathBuf>,
+    pub cmd: Vec<String>,
+    pub uid: Option<u32>,
+    pub user: Option<String>,
+    /// Seconds since the Unix epoch
+    pub start_time: u64,
+    /// Percentage of one core, measured since the previous refresh
+    pub cpu_usage: f32,
+    /// Resident memory in bytes
+    pub memory: u64,
+}
+
+impl ProcessInfo {
+    /// Process name and executable file name, the candidates for name filters
+    fn names(&self) -> impl Iterator<Item = &str> {
+        let exe_name = self.exe.as_deref().and_then(Path::file_name).and_then(|name| name.to_str());
+        std::iter::once(self.name.as_str()).chain(exe_name)
+    }
+}
+
+/// A compiled [`TargetSpec`]
+#[derive(Debug, Clone)]
+pub struct ProcessMatcher {
+    spec: TargetSpec,
+    name_glob: Option<Regex>,
+    name_regex: Option<Regex>,
+    cmdline_regex: Option<Regex>,
+}
+
+impl ProcessMatcher {
+    /// Compile the patterns in `spec`, which needs at least one filter
+    pub fn new(spec: &TargetSpec) -> Result<Self> {
+        if spec.is_empty() {
+            anyhow::bail!("target needs at least one of name, name_glob, name_regex, exe, cmdline, cmdline_regex, uid or user");
+        }
+
+        #[cfg(not(unix))]
+        if spec.uid.is_some() {
+            anyhow::bail!("uid filters are only supported on Unix, use user instead");
+        }
+
+        let regex = |pattern: &Option<String>, what: &str| {
+            pattern
+                .as_deref()
+                .map(|pattern| Regex::new(pattern).map_err(|e| anyhow::anyhow!("Invalid {} '{}': {}", what, pattern, e)))
+                .transpose()
+        };
+
+        Ok(Self {
+            spec: spec.clone(),
+            name_glob: spec.name_glob.as_deref().map(glob_to_regex).transpose()?,
+            name_regex: regex(&spec.name_regex, "name_regex")?,
+            cmdline_regex: regex(&spec.cmdline_regex, "cmdline_regex")?,
+        })
+    }
+
+    pub fn spec(&self) -> &TargetSpec {
+        &self.spec
+    }
+
+    /// Whether looking up user names is needed to match
+    fn needs_users(&self) -> bool {
+        self.spec.user.is_some()
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        let spec = &self.spec;
+
+        if let Some(name) = &spec.name {
+            let wanted = strip_exe(name);
+            if !process.names().any(|candidate| strip_exe(candidate).eq_ignore_ascii_case(wanted)) {
+                return false;
+            }
+        }
+        for regex in [&self.name_glob, &self.name_regex].into_iter().flatten() {
+            if !process.names().any(|candidate| regex.is_match(candidate)) {
+                return false;
+            }
+        }
+
+        if let Some(exe) = &spec.exe {
+            if process.exe.as_deref() != Some(Path::new(exe)) {
+                return false;
+            }
+        }
+
+        let cmdline = process.cmd.join(" ");
+        if spec.cmdline.as_ref().is_some_and(|text| !cmdline.contains(text.as_str())) {
+            return false;
+        }
+        if self.cmdline_regex.as_ref().is_some_and(|regex| !regex.is_match(&cmdline)) {
+            return false;
+        }
+
+        if spec.uid.is_some() && process.uid != spec.uid {
+            return false;
+        }
+        if spec.user.is_some() && process.user != spec.user {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// `notepad.exe` and `notepad` name the same program
+fn strip_exe(name: &str) -> &str {
+    match name.len().checked_sub(4) {
+        Some(stem) if name.is_char_boundary(stem) && name[stem..].eq_ignore_ascii_case(".exe") => &name[..stem],
+        _ => name,
+    }
+}
+
+/// Translate a `*`/`?` glob into an anchored, case-insensitive regex
+fn glob_to_regex(glob: &str) -> Result<Regex> {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Invalid name_glob '{}': {}", glob, e))
+}
+
+/// Whether keys go to one matching process or to every one of them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetMode {
+    /// The process picked by the selection policy
+    #[default]
+    Single,
+    /// Every matching process, including ones started later
+    All,
+}
+
+/// Which process to pick when several match the target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionPolicy {
+    #[default]
+    LowestPid,
+    /// The one that has been running longest
+    Oldest,
+    /// The one started most recently
+    Newest,
+    HighestCpu,
+    HighestMemory,
+    /// Refuse to pick, so a config never silently hits the wrong instance
+    FailIfAmbiguous,
+}
+
+impl SelectionPolicy {
+    pub const ALL: &'static [SelectionPolicy] = &[
+        SelectionPolicy::LowestPid,
+        SelectionPolicy::Oldest,
+        SelectionPolicy::Newest,
+        SelectionPolicy::HighestCpu,
+        SelectionPolicy::HighestMemory,
+        SelectionPolicy::FailIfAmbiguous,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SelectionPolicy::LowestPid => "lowest_pid",
+            SelectionPolicy::Oldest => "oldest",
+            SelectionPolicy::Newest => "newest",
+            SelectionPolicy::HighestCpu => "highest_cpu",
+            SelectionPolicy::HighestMemory => "highest_memory",
+            SelectionPolicy::FailIfAmbiguous => "fail_if_ambiguous",
+        }
+    }
+
+    /// Pick one of `candidates`, or `None` if there are none. Ties go to the
+    /// lowest PID so the same processes always give the same answer.
+    pub fn select<'a>(&self, candidates: &'a [ProcessInfo]) -> Result<Option<&'a ProcessInfo>> {
+        let mut sorted: Vec<&ProcessInfo> = candidates.iter().collect();
+        sorted.sort_by_key(|process| process.pid);
+
+        let best = match self {
+            SelectionPolicy::LowestPid => sorted.first().copied(),
+            SelectionPolicy::Oldest => sorted.iter().copied().min_by_key(|process| process.start_time),
+            SelectionPolicy::Newest => sorted.iter().copied().rev().max_by_key(|process| process.start_time),
+            SelectionPolicy::HighestCpu => sorted.iter().copied().rev().max_by(|a, b| a.cpu_usage.total_cmp(&b.cpu_usage)),
+            SelectionPolicy::HighestMemory => sorted.iter().copied().rev().max_by_key(|process| process.memory),
+            SelectionPolicy::FailIfAmbiguous => {
+                if sorted.len() > 1 {
+                    let pids: Vec<String> = sorted
+                        .iter()
+                        .map(|process| format!("{} ({})", process.pid, process.name))
+                        .collect();
+                    anyhow::bail!(
+                        "{} processes match: {}. Narrow the target or choose a selection policy.",
+                        sorted.len(),
+                        pids.join(", ")
+                    );
+                }
+                sorted.first().copied()
+            }
+        };
+
+        Ok(best)
+    }
+}
+
+impl fmt::Display for SelectionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SelectionPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_lowercase().replace('-', "_");
+        SelectionPolicy::ALL
+            .iter()
+            .copied()
+            .find(|policy| policy.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = SelectionPolicy::ALL.iter().map(SelectionPolicy::as_str).collect();
+                anyhow::anyhow!("Unknown selection policy '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// A process named by its PID, directly or through a pidfile, instead of searched for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PidSource {
+    Pid(u32),
+    /// A file holding the PID, read again whenever the process it named is gone
+    Pidfile(PathBuf),
+}
+
+impl PidSource {
+    /// The PID this source names right now
+    pub fn read(&self) -> Result<u32> {
+        match self {
+            PidSource::Pid(pid) => Ok(*pid),
+            PidSource::Pidfile(path) => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read pidfile '{}': {}", path.display(), e))?;
+                content
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid PID in pidfile '{}': {:?}", path.display(), content.trim()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for PidSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PidSource::Pid(pid) => write!(f, "PID {}", pid),
+            PidSource::Pidfile(path) => write!(f, "pidfile {}", path.display()),
+        }
+    }
+}
+
+/// Follows the process a [`PidSource`] names, without searching by name
+pub struct PidTarget {
+    source: PidSource,
+    finder: ProcessFinder,
+}
+
+impl PidTarget {
+    pub fn new(source: PidSource) -> Self {
+        Self {
+            source,
+            finder: ProcessFinder::new(),
+        }
+    }
+
+    pub fn source(&self) -> &PidSource {
+        &self.source
+    }
+
+    /// The named PID if that process is running
+    pub fn find(&mut self) -> Result<Option<u32>> {
+        let pid = self.source.read()?;
+        Ok(self.finder.is_alive(pid).then_some(pid))
+    }
+
+    /// The PID to send to instead of `pid`: `pid` itself while it runs, the
+    /// pidfile's new PID once it has restarted, or `None` while nothing runs
+    pub fn check(&mut self, pid: u32) -> Option<u32> {
+        if self.finder.is_alive(pid) {
+            return Some(pid);
+        }
+        match self.source {
+            PidSource::Pid(_) => None,
+            PidSource::Pidfile(_) => self.find().ok().flatten(),
+        }
+    }
+}
 
 pub struct ProcessFinder {
     system: System,
@@ -13,6 +403,12 @@ impl Clone for ProcessFinder {
     }
 }
 
+impl Default for ProcessFinder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ProcessFinder {
     pub fn new() -> Self {
         Self {
@@ -20,33 +416,101 @@ impl ProcessFinder {
         }
     }
 
+    /// Whether a process with `pid` is running; zombies don't count
+    pub fn is_alive(&mut self, pid: u32) -> bool {
+        let pid = Pid::from_u32(pid);
+        self.system.refresh_process_specifics(pid, ProcessRefreshKind::new())
+            && self.system.process(pid).is_some_and(|process| process.status() != ProcessStatus::Zombie)
+    }
+
+    /// Find a process named exactly `process_name` and return its window ID
     pub fn find_process_window(&mut self, process_name: &str) -> Result<Option<u64>> {
-        // Refresh all processes
-        self.system.refresh_processes();
+        self.find_process(&ProcessMatcher::new(&TargetSpec::name(process_name))?, SelectionPolicy::LowestPid)
+    }
 
-        let process_name_lower = process_name.to_lowercase();
+    /// Find the process `policy` picks among those `matcher` accepts and return its window ID
+    pub fn find_process(&mut self, matcher: &ProcessMatcher, policy: SelectionPolicy) -> Result<Option<u64>> {
+        let candidates = self.find_candidates(matcher, policy)?;
+        // For now the PID doubles as the window ID on every platform;
+        // the backends look up the process's windows from it
+        Ok(policy.select(&candidates)?.map(|process| process.pid as u64))
+    }
 
-        for (pid, process) in self.system.processes() {
-            let name = process.name().to_lowercase();
-            if name.contains(&process_name_lower) {
-                #[cfg(windows)]
-                {
-                    // For Windows, we'll use a simpler approach - just return the PID as window ID
-                    // The key sender can work with this approach
-                    return Ok(Some(pid.as_u32() as u64));
-                }
+    /// Every process `matcher` accepts, with what `policy` needs to choose between them.
+    ///
+    /// Blocks the thread while CPU usage is measured; async code should use
+    /// [`Self::find_candidates_async`].
+    pub fn find_candidates(&mut self, matcher: &ProcessMatcher, policy: SelectionPolicy) -> Result<Vec<ProcessInfo>> {
+        let candidates = self.find_matches(matcher)?;
+        if needs_cpu_sample(policy, &candidates) {
+            std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+            return self.find_matches(matcher);
+        }
+        Ok(candidates)
+    }
 
-                #[cfg(unix)]
-                {
-                    // For Unix, we'll use the PID as window ID for now
-                    return Ok(Some(pid.as_u32() as u64));
-                }
-            }
+    /// Like [`Self::find_candidates`], but waits for the CPU measurement without
+    /// holding up the runtime
+    pub async fn find_candidates_async(
+        &mut self,
+        matcher: &ProcessMatcher,
+        policy: SelectionPolicy,
+    ) -> Result<Vec<ProcessInfo>> {
+        let candidates = self.find_matches(matcher)?;
+        if needs_cpu_sample(policy, &candidates) {
+            tokio::time::sleep(MINIMUM_CPU_UPDATE_INTERVAL).await;
+            return self.find_matches(matcher);
         }
+        Ok(candidates)
+    }
 
-        Ok(None)
+    /// Every running process `matcher` accepts, lowest PID first
+    pub fn find_matches(&mut self, matcher: &ProcessMatcher) -> Result<Vec<ProcessInfo>> {
+        // A process that calls exec keeps its PID, so its exe and command
+        // line are read again every time
+        self.system.refresh_processes_specifics(
+            ProcessRefreshKind::new()
+                .with_exe(UpdateKind::Always)
+                .with_cmd(UpdateKind::Always)
+                .with_user(UpdateKind::OnlyIfNotSet)
+                .with_cpu()
+                .with_memory(),
+        );
+
+        let users = matcher.needs_users().then(Users::new_with_refreshed_list);
+
+        let mut matches: Vec<ProcessInfo> = self
+            .system
+            .processes()
+            .iter()
+            .map(|(pid, process)| {
+                let user_id = process.user_id();
+                ProcessInfo {
+                    pid: pid.as_u32(),
+                    name: process.name().to_string(),
+                    exe: process.exe().map(Path::to_path_buf),
+                    cmd: process.cmd().to_vec(),
+                    #[cfg(unix)]
+                    uid: user_id.map(|uid| **uid),
+                    #[cfg(not(unix))]
+                    uid: None,
+                    user: users
+                        .as_ref()
+                        .zip(user_id)
+                        .and_then(|(users, uid)| users.get_user_by_id(uid))
+                        .map(|user| user.name().to_string()),
+                    start_time: process.start_time(),
+                    cpu_usage: process.cpu_usage(),
+                    memory: process.memory(),
+                }
+            })
+            .filter(|process| matcher.matches(process))
+            .collect();
+
+        matches.sort_by_key(|process| process.pid);
+        Ok(matches)
     }
-    
+
     #[deprecated]
     #[allow(dead_code)]
     pub fn is_process_running(&mut self, process_name: &str) -> Result<bool> {
@@ -55,7 +519,7 @@ impl ProcessFinder {
 
         let process_name_lower = process_name.to_lowercase();
 
-        for (_pid, process) in self.system.processes() {
+        for process in self.system.processes().values() {
             let name = process.name().to_lowercase();
             if name.contains(&process_name_lower) {
                 return Ok(true);
@@ -64,4 +528,160 @@ impl ProcessFinder {
 
         Ok(false)
     }
-}
\ No newline at end of file
+}
+
+/// CPU usage is measured between two refreshes, and is only needed to choose
+/// among several candidates
+fn needs_cpu_sample(policy: SelectionPolicy, candidates: &[ProcessInfo]) -> bool {
+    policy == SelectionPolicy::HighestCpu && candidates.len() > 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str, exe: &str, cmd: &[&str]) -> ProcessInfo {
+        ProcessInfo {
+            pid: 100,
+            name: name.to_string(),
+            exe: Some(PathBuf::from(exe)),
+            cmd: cmd.iter().map(|arg| arg.to_string()).collect(),
+            uid: Some(1000),
+            user: Some("kyle".to_string()),
+            ..ProcessInfo::default()
+        }
+    }
+
+    fn matcher(json: &str) -> ProcessMatcher {
+        ProcessMatcher::new(&serde_json::from_str(json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_exact_name_does_not_match_prefixes() {
+        let java = process("java", "/usr/bin/java", &["java", "-jar", "server.jar"]);
+        let javaw = process("javaw", "/usr/bin/javaw", &["javaw"]);
+
+        let by_name = matcher(r#"{"name": "Java"}"#);
+        assert!(by_name.matches(&java));
+        assert!(!by_name.matches(&javaw));
+
+        // .exe is optional on either side
+        let notepad = process("notepad.exe", r"C:\Windows\notepad.exe", &[]);
+        assert!(matcher(r#"{"name": "notepad"}"#).matches(&notepad));
+        assert!(matcher(r#"{"name": "NOTEPAD.EXE"}"#).matches(&process("notepad", "/bin/notepad", &[])));
+
+        // Truncated Linux process names still match through the executable
+        let long = process("gnome-text-edit", "/usr/bin/gnome-text-editor", &[]);
+        assert!(matcher(r#"{"name": "gnome-text-editor"}"#).matches(&long));
+    }
+
+    #[test]
+    fn test_glob_and_regex_names() {
+        let javaw = process("javaw", "/usr/bin/javaw", &[]);
+        assert!(matcher(r#"{"name_glob": "JAVA*"}"#).matches(&javaw));
+        assert!(matcher(r#"{"name_glob": "jav?w"}"#).matches(&javaw));
+        assert!(!matcher(r#"{"name_glob": "java"}"#).matches(&javaw));
+        // Regex metacharacters in globs are literal
+        assert!(!matcher(r#"{"name_glob": "jav.w+"}"#).matches(&javaw));
+
+        assert!(matcher(r#"{"name_regex": "^java(w)?$"}"#).matches(&javaw));
+        assert!(!matcher(r#"{"name_regex": "^Java"}"#).matches(&javaw));
+    }
+
+    #[test]
+    fn test_exe_cmdline_and_user_filters() {
+        let server = process("java", "/opt/jdk/bin/java", &["java", "-jar", "minecraft_server.jar", "nogui"]);
+
+        assert!(matcher(r#"{"exe": "/opt/jdk/bin/java"}"#).matches(&server));
+        assert!(!matcher(r#"{"exe": "/usr/bin/java"}"#).matches(&server));
+        assert!(matcher(r#"{"cmdline": "minecraft_server.jar nogui"}"#).matches(&server));
+        assert!(!matcher(r#"{"cmdline": "Minecraft"}"#).matches(&server));
+        assert!(matcher(r#"{"cmdline_regex": "-jar \\S+server\\.jar"}"#).matches(&server));
+
+        assert!(matcher(r#"{"user": "kyle"}"#).matches(&server));
+        assert!(!matcher(r#"{"user": "root"}"#).matches(&server));
+        #[cfg(unix)]
+        {
+            assert!(matcher(r#"{"uid": 1000}"#).matches(&server));
+            assert!(!matcher(r#"{"uid": 0}"#).matches(&server));
+        }
+
+        // All filters have to match
+        assert!(matcher(r#"{"name": "java", "cmdline": "minecraft"}"#).matches(&server));
+        assert!(!matcher(r#"{"name": "java", "cmdline": "forge"}"#).matches(&server));
+    }
+
+    #[test]
+    fn test_invalid_targets() {
+        assert!(ProcessMatcher::new(&TargetSpec::default()).is_err());
+
+        let spec: TargetSpec = serde_json::from_str(r#"{"name_regex": "java("}"#).unwrap();
+        let error = ProcessMatcher::new(&spec).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid name_regex 'java('"), "{}", error);
+
+        assert!(serde_json::from_str::<TargetSpec>(r#"{"nmae": "java"}"#).is_err());
+    }
+
+    fn candidate(pid: u32, start_time: u64, cpu_usage: f32, memory: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: "java".to_string(),
+            start_time,
+            cpu_usage,
+            memory,
+            ..ProcessInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_selection_policies() {
+        let candidates = [
+            candidate(300, 1_000, 5.0, 100),
+            candidate(100, 2_000, 50.0, 100),
+            candidate(200, 1_000, 50.0, 300),
+        ];
+        let pick = |policy: SelectionPolicy| policy.select(&candidates).unwrap().unwrap().pid;
+
+        assert_eq!(pick(SelectionPolicy::LowestPid), 100);
+        // Ties go to the lowest PID
+        assert_eq!(pick(SelectionPolicy::Oldest), 200);
+        assert_eq!(pick(SelectionPolicy::Newest), 100);
+        assert_eq!(pick(SelectionPolicy::HighestCpu), 100);
+        assert_eq!(pick(SelectionPolicy::HighestMemory), 200);
+
+        let error = SelectionPolicy::FailIfAmbiguous.select(&candidates).unwrap_err().to_string();
+        assert!(error.starts_with("3 processes match: 100 (java), 200 (java), 300 (java)."), "{}", error);
+        assert_eq!(SelectionPolicy::FailIfAmbiguous.select(&candidates[..1]).unwrap().unwrap().pid, 300);
+
+        for policy in SelectionPolicy::ALL {
+            assert!(policy.select(&[]).unwrap().is_none());
+            assert_eq!(policy.to_string().parse::<SelectionPolicy>().unwrap(), *policy);
+        }
+        assert_eq!("highest-cpu".parse::<SelectionPolicy>().unwrap(), SelectionPolicy::HighestCpu);
+    }
+
+    #[test]
+    fn test_pid_sources() {
+        let own_pid = std::process::id();
+        let mut target = PidTarget::new(PidSource::Pid(own_pid));
+        assert_eq!(target.find().unwrap(), Some(own_pid));
+        assert_eq!(target.check(own_pid), Some(own_pid));
+        assert!(!ProcessFinder::new().is_alive(u32::MAX));
+
+        let mut pidfile = tempfile::NamedTempFile::new().unwrap();
+        let source = PidSource::Pidfile(pidfile.path().to_path_buf());
+        assert!(source.read().unwrap_err().to_string().starts_with("Invalid PID in pidfile"));
+
+        // A process that is gone is replaced by whatever the pidfile names now
+        std::io::Write::write_all(&mut pidfile, format!("{}\n", own_pid).as_bytes()).unwrap();
+        let mut target = PidTarget::new(source);
+        assert_eq!(target.check(u32::MAX), Some(own_pid));
+        assert_eq!(PidTarget::new(PidSource::Pid(u32::MAX)).check(u32::MAX), None);
+    }
+
+    #[test]
+    fn test_target_display() {
+        let spec: TargetSpec = serde_json::from_str(r#"{"name": "java", "cmdline": "minecraft", "uid": 1000}"#).unwrap();
+        assert_eq!(spec.to_string(), r#"name "java", cmdline contains "minecraft", uid 1000"#);
+    }
+}
@@ -0,0 +1,318 @@
+//! Delays that vary from one use to the next, for less regular timing.
+//!
+//! Every interval in a [`Config`] is a [`Delay`]: either a fixed duration
+//! (`"1s"`), a range (`"900ms..1200ms"`) or a duration with jitter
+//! (`"1s±10%"`). A [`Timing`] draws the actual durations, optionally from a
+//! seeded generator so a run can be reproduced.
+
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::backend::DEFAULT_HOLD;
+use crate::config::{duration_to_string, parse_duration, Config};
+
+/// A delay drawn from `min..=max` each time it is used; fixed when both are equal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delay {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Delay {
+    pub const fn fixed(duration: Duration) -> Self {
+        Self { min: duration, max: duration }
+    }
+
+    pub fn range(min: Duration, max: Duration) -> Result<Self> {
+        if min > max {
+            anyhow::bail!("Range start {} is after its end {}", duration_to_string(min), duration_to_string(max));
+        }
+        Ok(Self { min, max })
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        self.min == self.max
+    }
+
+    /// Widen the range by `percent` of each end, in both directions
+    pub fn with_jitter(self, percent: u32) -> Self {
+        let spread = |duration: Duration| duration.mul_f64(f64::from(percent) / 100.0);
+        Self {
+            min: self.min.saturating_sub(spread(self.min)),
+            max: self.max.saturating_add(spread(self.max)),
+        }
+    }
+
+    /// Parse `"1s"`, `"900ms..1200ms"` or `"1s±10%"` (also written `"1s+-10%"`)
+    pub fn parse(s: &str) -> Result<Self> {
+        if let Some((min, max)) = s.split_once("..") {
+            return Self::range(parse_duration(min)?, parse_duration(max)?);
+        }
+
+        if let Some((base, percent)) = s.split_once('±').or_else(|| s.split_once("+-")) {
+            return Ok(Self::fixed(parse_duration(base)?).with_jitter(parse_percent(percent)?));
+        }
+
+        Ok(Self::fixed(parse_duration(s)?))
+    }
+}
+
+impl From<Duration> for Delay {
+    fn from(duration: Duration) -> Self {
+        Self::fixed(duration)
+    }
+}
+
+/// A delay equals a duration when it is fixed to exactly that duration
+impl PartialEq<Duration> for Delay {
+    fn eq(&self, other: &Duration) -> bool {
+        self.min == *other && self.max == *other
+    }
+}
+
+/// Written back in a form [`Delay::parse`] accepts; jitter is shown as the range it covers
+impl fmt::Display for Delay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_fixed() {
+            f.write_str(&duration_to_string(self.min))
+        } else {
+            write!(f, "{}..{}", duration_to_string(self.min), duration_to_string(self.max))
+        }
+    }
+}
+
+/// Parse a percentage between 0 and 100 such as `"10%"` or `"10"`
+pub fn parse_percent(s: &str) -> Result<u32> {
+    let s = s.trim();
+    let percent: u32 = s.strip_suffix('%').unwrap_or(s).trim().parse()
+        .map_err(|_| anyhow::anyhow!("Invalid percentage: {}", s))?;
+    if percent > 100 {
+        anyhow::bail!("Percentage must be at most 100%: {}", s);
+    }
+    Ok(percent)
+}
+
+/// How durations are spread over a range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Distribution {
+    /// Every duration in the range is equally likely
+    #[default]
+    Uniform,
+    /// Bell curve around the middle of the range, clamped to its ends
+    Normal,
+}
+
+/// Draws durations for [`Delay`]s. Clones share one random number generator;
+/// [`Timing::for_stream`] gives each concurrent user one of its own.
+#[derive(Clone)]
+pub struct Timing {
+    rng: Arc<Mutex<StdRng>>,
+    seed: Option<u64>,
+    distribution: Distribution,
+    jitter: u32,
+    hold_time: Delay,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl Timing {
+    /// Uniform timing without jitter. The same `seed` draws the same durations;
+    /// without one the generator is seeded randomly.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            rng: new_rng(seed),
+            seed,
+            distribution: Distribution::Uniform,
+            jitter: 0,
+            hold_time: Delay::fixed(DEFAULT_HOLD),
+        }
+    }
+
+    /// The timing settings of `config`: `seed`, `distribution`, `jitter` and `hold_time`
+    pub fn from_config(config: &Config) -> Self {
+        let timing = Self::new(config.seed)
+            .with_distribution(config.distribution)
+            .with_jitter(config.jitter);
+
+        match config.hold_time {
+            Some(hold_time) => timing.with_hold_time(hold_time),
+            None => timing,
+        }
+    }
+
+    pub fn with_distribution(self, distribution: Distribution) -> Self {
+        Self { distribution, ..self }
+    }
+
+    /// Add `percent` jitter to every fixed delay
+    pub fn with_jitter(self, percent: u32) -> Self {
+        Self { jitter: percent, ..self }
+    }
+
+    /// How long keys and buttons stay down during a tap or click
+    pub fn with_hold_time(self, hold_time: Delay) -> Self {
+        Self { hold_time, ..self }
+    }
+
+    /// The same settings with a generator of its own for stream `index`, such
+    /// as one independent key. With a seed, each stream draws the same
+    /// durations on every run however the streams take turns.
+    pub fn for_stream(&self, index: u64) -> Self {
+        Self {
+            rng: new_rng(self.seed.map(|seed| seed ^ index)),
+            ..self.clone()
+        }
+    }
+
+    /// Draw a duration for `delay`
+    pub fn sample(&self, delay: Delay) -> Duration {
+        let delay = if delay.is_fixed() { delay.with_jitter(self.jitter) } else { delay };
+        if delay.is_fixed() {
+            return delay.min;
+        }
+
+        let mut rng = self.rng.lock().unwrap();
+        let fraction = match self.distribution {
+            Distribution::Uniform => rng.gen_range(0.0..=1.0),
+            // Three standard deviations either side of the middle reach the ends
+            Distribution::Normal => (0.5 + standard_normal(&mut rng) / 6.0).clamp(0.0, 1.0),
+        };
+
+        delay.min + (delay.max - delay.min).mul_f64(fraction)
+    }
+
+    /// Draw how long to hold a key or button down during a tap or click
+    pub fn hold(&self) -> Duration {
+        self.sample(self.hold_time)
+    }
+}
+
+fn new_rng(seed: Option<u64>) -> Arc<Mutex<StdRng>> {
+    let rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    Arc::new(Mutex::new(rng))
+}
+
+/// A normally distributed value with mean 0 and standard deviation 1 (Box-Muller)
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    let u1: f64 = 1.0 - rng.r#gen::<f64>();
+    let u2: f64 = rng.r#gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_parsing() {
+        assert_eq!(Delay::parse("1s").unwrap(), Duration::from_secs(1));
+        assert_eq!(
+            Delay::parse("900ms..1200ms").unwrap(),
+            Delay::range(Duration::from_millis(900), Duration::from_millis(1200)).unwrap()
+        );
+        assert_eq!(
+            Delay::parse(" 1s ± 10% ").unwrap(),
+            Delay::range(Duration::from_millis(900), Duration::from_millis(1100)).unwrap()
+        );
+        assert_eq!(Delay::parse("1s+-10%").unwrap(), Delay::parse("1s±10%").unwrap());
+        assert_eq!(Delay::parse(".5s..1.5s").unwrap().max, Duration::from_millis(1500));
+
+        assert!(Delay::parse("2s..1s").is_err());
+        assert!(Delay::parse("1s..").is_err());
+        assert!(Delay::parse("1s±200%").is_err());
+        assert!(Delay::parse("1s±x%").is_err());
+    }
+
+    #[test]
+    fn test_delay_round_trip() {
+        for text in ["1s", "900ms..1.2s", "0ms..250us", "1m30s..2m"] {
+            let delay = Delay::parse(text).unwrap();
+            assert_eq!(delay.to_string(), text);
+            assert_eq!(Delay::parse(&delay.to_string()).unwrap(), delay);
+        }
+        assert_eq!(Delay::parse("1s±10%").unwrap().to_string(), "900ms..1.1s");
+    }
+
+    #[test]
+    fn test_sample_stays_in_range() {
+        let delay = Delay::parse("900ms..1200ms").unwrap();
+        for distribution in [Distribution::Uniform, Distribution::Normal] {
+            let timing = Timing::new(Some(7)).with_distribution(distribution);
+            let samples: Vec<Duration> = (0..500).map(|_| timing.sample(delay)).collect();
+
+            assert!(samples.iter().all(|d| (delay.min..=delay.max).contains(d)));
+            assert!(samples.iter().any(|d| *d != samples[0]), "{:?} never varied", distribution);
+        }
+    }
+
+    #[test]
+    fn test_normal_distribution_centres_on_the_middle() {
+        let delay = Delay::parse("0ms..1000ms").unwrap();
+        let timing = Timing::new(Some(1)).with_distribution(Distribution::Normal);
+        let middle = (0..1000)
+            .map(|_| timing.sample(delay))
+            .filter(|d| (Duration::from_millis(250)..=Duration::from_millis(750)).contains(d))
+            .count();
+
+        // About 87% of a normal distribution lies within 1.5 standard deviations
+        assert!(middle > 800, "only {} of 1000 samples near the middle", middle);
+    }
+
+    #[test]
+    fn test_seed_reproduces_samples() {
+        let delay = Delay::parse("1ms..1s").unwrap();
+        let draw = |seed| {
+            let timing = Timing::new(Some(seed));
+            (0..20).map(|_| timing.sample(delay)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(draw(42), draw(42));
+        assert_ne!(draw(42), draw(43));
+    }
+
+    #[test]
+    fn test_streams_draw_independently() {
+        let delay = Delay::parse("1ms..1s").unwrap();
+        let timing = Timing::new(Some(42));
+
+        // Interleaving draws from two streams doesn't change either one's durations
+        let (a, b) = (timing.for_stream(0), timing.for_stream(1));
+        let interleaved: Vec<_> = (0..10).flat_map(|_| [a.sample(delay), b.sample(delay)]).collect();
+        let (a, b) = (timing.for_stream(0), timing.for_stream(1));
+        let b_first: Vec<_> = (0..10).map(|_| b.sample(delay)).collect();
+        let a_after: Vec<_> = (0..10).map(|_| a.sample(delay)).collect();
+
+        assert_eq!(interleaved.iter().step_by(2).copied().collect::<Vec<_>>(), a_after);
+        assert_eq!(interleaved.iter().skip(1).step_by(2).copied().collect::<Vec<_>>(), b_first);
+        assert_ne!(a_after, b_first);
+    }
+
+    #[test]
+    fn test_jitter_and_hold_time() {
+        let timing = Timing::new(Some(3));
+        assert_eq!(timing.sample(Delay::fixed(Duration::from_secs(1))), Duration::from_secs(1));
+        assert_eq!(timing.hold(), DEFAULT_HOLD);
+
+        let timing = timing.with_jitter(20).with_hold_time(Delay::parse("10ms..20ms").unwrap());
+        for _ in 0..100 {
+            let sample = timing.sample(Delay::fixed(Duration::from_secs(1)));
+            assert!((Duration::from_millis(800)..=Duration::from_millis(1200)).contains(&sample));
+            // Jitter only widens fixed delays
+            let hold = timing.hold();
+            assert!((Duration::from_millis(10)..=Duration::from_millis(20)).contains(&hold));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! `--watch`: reload the configuration whenever its file changes.
+
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+use crate::automation::RunControl;
+
+/// How often the watched file is checked by default
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What identifies one version of the file on disk
+type Fingerprint = Option<(SystemTime, u64)>;
+
+fn fingerprint(path: &Path) -> Fingerprint {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Poll `path` every `poll_interval` and request a reload through `control`
+/// once a change has settled.
+///
+/// A change only counts once the file looks the same on two polls in a row,
+/// so editors that write in several steps trigger a single reload. Rejected
+/// configs are reported and the running one is kept.
+pub fn spawn_config_watcher(path: &Path, control: RunControl, poll_interval: Duration) -> JoinHandle<()> {
+    let path: PathBuf = path.to_path_buf();
+
+    tokio::spawn(async move {
+        let mut loaded = fingerprint(&path);
+        let mut previous = loaded;
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(poll_interval) => {}
+                _ = control.shutdown.wait() => break,
+            }
+
+            let current = fingerprint(&path);
+            let settled = current == previous;
+            previous = current;
+
+            // Missing while an editor replaces it, or unchanged since the last load
+            if current.is_none() || !settled || current == loaded {
+                continue;
+            }
+
+            loaded = current;
+            println!("{} {} changed, reloading", "👀".blue(), path.display().to_string().cyan());
+
+            if let Err(e) = control.request_reload().await {
+                eprintln!("{} Keeping the previous configuration: {}", "✗".red(), e);
+            }
+        }
+    })
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
-use process_key_sender::config::{Config, parse_duration};
+use process_key_sender::backend::{BackendKind, DEFAULT_HOLD};
+use process_key_sender::config::{Action, CliOverrides, Config, parse_duration};
 use std::time::Duration;
 use tempfile::NamedTempFile;
 use std::io::Write;
@@ -77,9 +78,9 @@ fn test_key_sequence_config() {
     
     assert_eq!(config.process_name, "notepad.exe");
     assert_eq!(config.key_sequence.len(), 3);
-    assert_eq!(config.key_sequence[0].key, "1");
+    assert_eq!(config.key_sequence[0].action, Action::Key("1".to_string()));
     assert_eq!(config.key_sequence[0].interval_after, Duration::from_millis(500));
-    assert_eq!(config.key_sequence[2].key, "space");
+    assert_eq!(config.key_sequence[2].action, Action::Key("space".to_string()));
     assert_eq!(config.key_sequence[2].interval_after, Duration::from_secs(1));
     assert_eq!(config.max_retries, 5);
     assert!(!config.verbose);
@@ -139,12 +140,16 @@ fn test_duration_parsing_edge_cases() {
     assert_eq!(parse_duration("1000").unwrap(), Duration::from_millis(1000));
     assert_eq!(parse_duration("5S").unwrap(), Duration::from_secs(5)); // Case insensitive
     assert_eq!(parse_duration(" 2m ").unwrap(), Duration::from_secs(120)); // Whitespace
+    assert_eq!(parse_duration("1.5S").unwrap(), Duration::from_millis(1500)); // Fractions
+    assert_eq!(parse_duration("1H30M").unwrap(), Duration::from_secs(5400)); // Compound
     
     // Invalid cases
     assert!(parse_duration("").is_err());
     assert!(parse_duration("abc").is_err());
     assert!(parse_duration("1000x").is_err());
     assert!(parse_duration("-1000ms").is_err());
+    assert!(parse_duration("1.5.0s").is_err());
+    assert!(parse_duration("30s 1m").is_err());
 }
 
 #[test]
@@ -152,6 +157,15 @@ fn test_config_validation_errors() {
     // Empty process name
     let mut config = Config {
         process_name: "".to_string(),
+        target: None,
+        pid: None,
+        pidfile: None,
+        select: Default::default(),
+        target_mode: Default::default(),
+        broadcast: Default::default(),
+        rescan_interval: Duration::from_secs(2).into(),
+        on_target_exit: Default::default(),
+        target_exit_code: 3,
         key_sequence: vec![],
         independent_keys: vec![],
         max_retries: 10,
@@ -159,7 +173,16 @@ fn test_config_validation_errors() {
         verbose: false,
         loop_sequence: true,
         repeat_count: 0,
-        restore_focus: true
+        restore_focus: true,
+        backend: BackendKind::Auto,
+        mock_output: None,
+        dry_run: false,
+        control_socket: None,
+        layout: None,
+        seed: None,
+        distribution: Default::default(),
+        jitter: 0,
+        hold_time: None,
     };
     
     assert!(config.validate().is_err());
@@ -171,7 +194,7 @@ fn test_config_validation_errors() {
     // Zero retries
     config.independent_keys.push(process_key_sender::config::IndependentKey {
         key: "space".to_string(),
-        interval: Duration::from_millis(1000),
+        interval: Duration::from_millis(1000).into(),
     });
     config.max_retries = 0;
     assert!(config.validate().is_err());
@@ -264,4 +287,1248 @@ fn test_mixed_duration_formats() {
     assert_eq!(config.key_sequence[2].interval_after, Duration::from_millis(2000));
     
     assert!(config.validate().is_ok());
-}
\ No newline at end of file
+}
+#[cfg(target_os = "linux")]
+#[test]
+fn test_uinput_backend_emits_key_events() -> Result<()> {
+    use evdev::{InputEventKind, Key};
+    use process_key_sender::backend::{BackendOptions, InputBackend, UinputBackend, uinput::DEVICE_NAME};
+    use std::sync::mpsc;
+
+    // Needs the uinput module and write access to /dev/uinput
+    if std::fs::OpenOptions::new().write(true).open("/dev/uinput").is_err() {
+        eprintln!("skipping: /dev/uinput is not available");
+        return Ok(());
+    }
+
+    let backend = UinputBackend::new(&BackendOptions::default())?;
+
+    let (_, mut device) = evdev::enumerate()
+        .find(|(_, device)| device.name() == Some(DEVICE_NAME))
+        .expect("virtual keyboard should be listed");
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut events = Vec::new();
+        while events.len() < 2 {
+            for event in device.fetch_events().unwrap() {
+                if let InputEventKind::Key(key) = event.kind() {
+                    events.push((key, event.value()));
+                }
+            }
+        }
+        let _ = tx.send(events);
+    });
+
+    backend.tap(0, "a", DEFAULT_HOLD)?;
+
+    let events = rx.recv_timeout(Duration::from_secs(2))?;
+    assert_eq!(events, vec![(Key::KEY_A, 1), (Key::KEY_A, 0)]);
+
+    Ok(())
+}
+
+/// The X11 tests tag their windows with the same PID, so they must not overlap
+#[cfg(target_os = "linux")]
+static X11_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Open a window tagged with our own PID that listens for key presses
+#[cfg(target_os = "linux")]
+fn create_pid_window() -> Result<(x11rb::rust_connection::RustConnection, u32)> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::Event;
+    use x11rb::protocol::xproto::{
+        AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, PropMode, WindowClass,
+    };
+    use x11rb::wrapper::ConnectionExt as _;
+
+    let (conn, screen_num) = x11rb::connect(None)?;
+    let screen = &conn.setup().roots[screen_num];
+    let window = conn.generate_id()?;
+    conn.create_window(
+        x11rb::COPY_DEPTH_FROM_PARENT,
+        window,
+        screen.root,
+        0, 0, 100, 100, 0,
+        WindowClass::INPUT_OUTPUT,
+        0,
+        &CreateWindowAux::new().event_mask(EventMask::KEY_PRESS | EventMask::STRUCTURE_NOTIFY),
+    )?;
+    let pid_atom = conn.intern_atom(false, b"_NET_WM_PID")?.reply()?.atom;
+    conn.change_property32(PropMode::REPLACE, window, pid_atom, AtomEnum::CARDINAL, &[std::process::id()])?;
+    conn.map_window(window)?;
+    conn.flush()?;
+
+    while !matches!(conn.wait_for_event()?, Event::MapNotify(_)) {}
+
+    Ok((conn, window))
+}
+
+/// Wait up to two seconds for a KeyPress on `conn`
+#[cfg(target_os = "linux")]
+fn wait_for_key_press(conn: &x11rb::rust_connection::RustConnection) -> Result<x11rb::protocol::xproto::KeyPressEvent> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::Event;
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(2);
+    loop {
+        match conn.poll_for_event()? {
+            Some(Event::KeyPress(event)) => return Ok(event),
+            Some(_) => {}
+            None if std::time::Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            None => anyhow::bail!("no KeyPress received by the target window"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_xtest_backend_delivers_to_target_window() -> Result<()> {
+    use process_key_sender::backend::{BackendOptions, InputBackend, XTestBackend};
+
+    // Run under e.g. `xvfb-run cargo test` to exercise this
+    if std::env::var_os("DISPLAY").is_none() {
+        eprintln!("skipping: no X display");
+        return Ok(());
+    }
+
+    let _guard = X11_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (conn, window) = create_pid_window()?;
+
+    let backend = XTestBackend::new(&BackendOptions { restore_focus: false, ..Default::default() })?;
+    backend.tap(std::process::id() as u64, "a", DEFAULT_HOLD)?;
+
+    assert_eq!(wait_for_key_press(&conn)?.event, window);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_xsendevent_backend_delivers_without_focus() -> Result<()> {
+    use process_key_sender::backend::{BackendOptions, InputBackend, XSendEventBackend};
+
+    if std::env::var_os("DISPLAY").is_none() {
+        eprintln!("skipping: no X display");
+        return Ok(());
+    }
+
+    let _guard = X11_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (conn, window) = create_pid_window()?;
+    let pid = std::process::id() as u64;
+
+    let backend = XSendEventBackend::new(&BackendOptions::default())?;
+
+    let reports = backend.delivery_report(pid)?;
+    assert!(reports.iter().any(|report| report.window == window as u64 && report.likely_works));
+
+    backend.chord(pid, &["shift", "a"], DEFAULT_HOLD)?;
+
+    // The shift press arrives first, then `a` with the shift bit in its state
+    let shift = wait_for_key_press(&conn)?;
+    let a = wait_for_key_press(&conn)?;
+    assert_eq!(shift.event, window);
+    assert_eq!(a.event, window);
+    assert_eq!(u16::from(a.state) & 1, 1);
+
+    Ok(())
+}
+
+fn mock_config(json: &str) -> Config {
+    let mut config: Config = serde_json::from_str(json).unwrap();
+    config.backend = BackendKind::Mock;
+    config
+}
+
+#[tokio::test]
+async fn test_key_sequence_event_stream() -> Result<()> {
+    use process_key_sender::automation::{RunControl, run_key_sequence};
+    use process_key_sender::backend::mock::KeyEventKind::{Press, Release};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "notepad.exe",
+        "key_sequence": [
+            {"key": "1", "interval_after": "1ms"},
+            {"key": "ctrl+s", "interval_after": "1ms"}
+        ],
+        "repeat_count": 2
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+
+    run_key_sequence(&config, &key_sender, 42, &RunControl::new()).await?;
+
+    let once = [
+        ("1", Press), ("1", Release),
+        ("ctrl", Press), ("s", Press), ("s", Release), ("ctrl", Release),
+    ];
+    let expected: Vec<(String, _)> = once.iter().chain(once.iter())
+        .map(|(key, kind)| (key.to_string(), *kind))
+        .collect();
+    assert_eq!(mock.key_stream(), expected);
+
+    let events = mock.events();
+    assert!(events.iter().all(|event| event.target == 42));
+    assert!(events.windows(2).all(|pair| pair[0].at <= pair[1].at));
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_independent_keys_event_stream() -> Result<()> {
+    use process_key_sender::automation::{RunControl, run_independent_keys};
+    use process_key_sender::backend::mock::KeyEventKind::Press;
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game.exe",
+        "independent_keys": [
+            {"key": "a", "interval": "100ms"},
+            {"key": "b", "interval": "250ms"}
+        ]
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let control = RunControl::new();
+
+    let stopper = control.shutdown.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(560)).await;
+        stopper.trigger();
+    });
+
+    run_independent_keys(&config, &key_sender, 7, &control).await?;
+
+    let stream = mock.key_stream();
+    let presses = |key: &str| stream.iter().filter(|(k, kind)| k == key && *kind == Press).count();
+
+    // Sent at 0, 100, ..., 500ms and at 0, 250, 500ms respectively
+    assert_eq!(presses("a"), 6);
+    assert_eq!(presses("b"), 3);
+    assert_eq!(stream.len(), 2 * (6 + 3));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_backend_from_config_writes_stream() -> Result<()> {
+    use process_key_sender::KeySender;
+    use process_key_sender::automation::{RunControl, run_key_sequence};
+
+    let output = NamedTempFile::new()?;
+    let mut config = mock_config(r#"
+    {
+        "process_name": "notepad.exe",
+        "key_sequence": [
+            {"key": "space", "interval_after": "1ms"}
+        ],
+        "loop_sequence": false
+    }
+    "#);
+    config.mock_output = Some(output.path().to_str().unwrap().to_string());
+
+    let key_sender = KeySender::from_config(&config)?;
+    assert_eq!(key_sender.backend_name(), "mock");
+
+    run_key_sequence(&config, &key_sender, 3, &RunControl::new()).await?;
+
+    let content = std::fs::read_to_string(output.path())?;
+    let lines: Vec<serde_json::Value> = content
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["key"], "space");
+    assert_eq!(lines[0]["event"], "press");
+    assert_eq!(lines[0]["target"], 3);
+    assert_eq!(lines[1]["event"], "release");
+    assert!(lines[0]["at_us"].is_u64());
+
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_never_creates_real_backend() -> Result<()> {
+    use process_key_sender::KeySender;
+
+    let mut config: Config = serde_json::from_str(r#"
+    {
+        "process_name": "production-app.exe",
+        "key_sequence": [{"key": "ctrl+s", "interval_after": "1s"}],
+        "dry_run": true
+    }
+    "#)?;
+    assert!(config.dry_run);
+
+    // Not available on this platform, but a dry run must not care
+    #[cfg(windows)]
+    { config.backend = BackendKind::Uinput; }
+    #[cfg(not(windows))]
+    { config.backend = BackendKind::SendInput; }
+
+    let key_sender = KeySender::from_config(&config)?;
+    assert_eq!(key_sender.backend_name(), "dry-run");
+    key_sender.send_key_to_window(1234, "ctrl+s")?;
+
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_validates_against_configured_backend() -> Result<()> {
+    use process_key_sender::KeySender;
+
+    let errors = |backend: &str, action: &str| -> Result<Vec<String>> {
+        let json = format!(
+            r#"{{"process_name": "app", "backend": "{}", "dry_run": true, "key_sequence": [{}]}}"#,
+            backend, action
+        );
+        let config: Config = serde_json::from_str(&json)?;
+        let key_sender = KeySender::from_config(&config)?;
+        Ok(config.check_with(&key_sender).errors.iter().map(ToString::to_string).collect())
+    };
+
+    let move_to = r#"{"move_to": [10, 20], "interval_after": "1s"}"#;
+    assert_eq!(errors("uinput", move_to)?, [
+        "key_sequence[0]: move mouse to 10,20: The uinput backend can only move the mouse relatively (use move_by)"
+    ]);
+    assert!(errors("xtest", move_to)?.is_empty());
+
+    let evdev = r#"{"key": "evdev:30", "interval_after": "1s"}"#;
+    assert!(errors("uinput", evdev)?.is_empty());
+    assert_eq!(errors("sendinput", evdev)?, [
+        "key_sequence[0]: evdev:30: The sendinput backend can't send 'evdev:30', use a vk: or sc: code"
+    ]);
+    assert_eq!(errors("xtest", evdev)?.len(), 1);
+    assert!(errors("mock", evdev)?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pause_holds_independent_keys() -> Result<()> {
+    use process_key_sender::automation::{RunControl, run_independent_keys};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game.exe",
+        "independent_keys": [{"key": "a", "interval": "100ms"}]
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let control = RunControl::new();
+
+    let remote = control.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(remote.pause.toggle());
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(!remote.pause.toggle());
+        tokio::time::sleep(Duration::from_millis(110)).await;
+        remote.shutdown.trigger();
+    });
+
+    run_independent_keys(&config, &key_sender, 1, &control).await?;
+
+    // Sent at 0, 100 and 200ms, held from 250ms, then sent again at 650 and 750ms
+    assert_eq!(mock.key_stream().len(), 2 * 5);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_control_socket_steers_running_automation() -> Result<()> {
+    use process_key_sender::automation::{RunControl, Targets, run_with_reload};
+    use process_key_sender::control::{ControlCommand, ControlServer, send_command};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game.exe",
+        "independent_keys": [{"key": "a", "interval": "10ms"}]
+    }
+    "#);
+    let reloaded = mock_config(r#"
+    {
+        "process_name": "game.exe",
+        "independent_keys": [{"key": "b", "interval": "10ms"}]
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let control = RunControl::new();
+
+    let dir = tempfile::tempdir()?;
+    let socket = dir.path().join("pks.sock");
+    let server = ControlServer::start(&socket, control.clone()).await?;
+
+    // A second instance must not take over the socket
+    assert!(ControlServer::start(&socket, RunControl::new()).await.is_err());
+
+    let client = async {
+        let response = send_command(&socket, ControlCommand::Pause).await?;
+        assert!(response.ok);
+
+        let status = send_command(&socket, ControlCommand::Status).await?.status.unwrap();
+        assert!(status.paused);
+
+        // Nothing is sent while paused
+        mock.clear();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(mock.events().is_empty());
+
+        send_command(&socket, ControlCommand::Resume).await?;
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        let response = send_command(&socket, ControlCommand::Reload).await?;
+        assert!(response.ok, "{:?}", response.message);
+        mock.clear();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        let status = send_command(&socket, ControlCommand::Status).await?.status.unwrap();
+        assert!(!status.paused);
+        assert!(status.keys_sent > 0);
+
+        send_command(&socket, ControlCommand::Stop).await?;
+        anyhow::Ok(())
+    };
+
+    let targets = Targets::from(1);
+    let (run, client) = tokio::join!(
+        run_with_reload(config, &key_sender, &targets, &control, || Ok(reloaded.clone())),
+        client
+    );
+    run?;
+    client?;
+
+    assert!(mock.events().iter().all(|event| event.key == "b"));
+
+    drop(server);
+    assert!(!socket.exists());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_held_keys_released_on_shutdown() -> Result<()> {
+    use process_key_sender::automation::{RunControl, Targets, run_configured};
+    use process_key_sender::backend::mock::KeyEventKind::{Press, Release};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game.exe",
+        "independent_keys": [{"key": "a", "interval": "1s"}]
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    key_sender.press_key(1, "shift")?;
+    key_sender.press_key(1, "ctrl")?;
+
+    let control = RunControl::new();
+    control.shutdown.trigger();
+    run_configured(&config, &key_sender, &Targets::from(1), &control).await?;
+
+    assert!(key_sender.held_keys().is_empty());
+    assert_eq!(mock.key_stream()[..2], [
+        ("shift".to_string(), Press),
+        ("ctrl".to_string(), Press),
+    ]);
+    assert_eq!(mock.key_stream()[2..], [
+        ("ctrl".to_string(), Release),
+        ("shift".to_string(), Release),
+    ]);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_signals_toggle_pause_and_reload() -> Result<()> {
+    use process_key_sender::automation::{RunControl, Targets, run_with_reload};
+    use process_key_sender::control::spawn_signal_handlers;
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn raise(signal: &str) {
+        let status = std::process::Command::new("kill")
+            .args([signal, &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game.exe",
+        "independent_keys": [{"key": "a", "interval": "10ms"}]
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let control = RunControl::new();
+    spawn_signal_handlers(&control)?;
+
+    let reloads = AtomicU32::new(0);
+    let load_config = || {
+        reloads.fetch_add(1, Ordering::SeqCst);
+        Ok(config.clone())
+    };
+
+    let signals = async {
+        raise("-USR1");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(control.pause.is_paused());
+
+        raise("-USR1");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!control.pause.is_paused());
+
+        raise("-HUP");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        control.shutdown.trigger();
+    };
+
+    let targets = Targets::from(1);
+    let (run, ()) = tokio::join!(
+        run_with_reload(config.clone(), &key_sender, &targets, &control, load_config),
+        signals
+    );
+    run?;
+
+    assert_eq!(reloads.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watch_swaps_config_and_keeps_old_on_invalid_edit() -> Result<()> {
+    use process_key_sender::automation::{RunControl, Targets, run_with_reload};
+    use process_key_sender::watch::spawn_config_watcher;
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let write_config = |path: &std::path::Path, key: &str| {
+        let json = format!(
+            r#"{{"process_name": "game.exe", "independent_keys": [{{"key": "{}", "interval": "10ms"}}]}}"#,
+            key
+        );
+        std::fs::write(path, json).unwrap();
+    };
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config.json");
+    write_config(&path, "a");
+
+    let load_config = || {
+        let mut config = Config::from_file(path.to_str().unwrap())?;
+        config.validate()?;
+        config.backend = BackendKind::Mock;
+        Ok(config)
+    };
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let control = RunControl::new();
+    spawn_config_watcher(&path, control.clone(), Duration::from_millis(20));
+
+    let edits = async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        write_config(&path, "b");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        mock.clear();
+
+        // An invalid edit leaves "b" running
+        std::fs::write(&path, r#"{"process_name": "game.exe"}"#).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        control.shutdown.trigger();
+    };
+
+    let targets = Targets::from(1);
+    let (run, ()) = tokio::join!(
+        run_with_reload(load_config()?, &key_sender, &targets, &control, load_config),
+        edits
+    );
+    run?;
+
+    assert!(!mock.events().is_empty());
+    assert!(mock.events().iter().all(|event| event.key == "b"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_chord_sequence_event_stream() -> Result<()> {
+    use process_key_sender::automation::{RunControl, run_key_sequence};
+    use process_key_sender::backend::mock::KeyEventKind::Press;
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "editor",
+        "key_sequence": [{"key": "Control+K ctrl+c", "interval_after": "1ms"}],
+        "loop_sequence": false
+    }
+    "#);
+    config.validate()?;
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    run_key_sequence(&config, &key_sender, 1, &RunControl::new()).await?;
+
+    let stream: Vec<_> = mock.key_stream().into_iter().map(|(key, kind)| (key, kind == Press)).collect();
+    let expected = [("ctrl", true), ("k", true), ("k", false), ("ctrl", false),
+                    ("ctrl", true), ("c", true), ("c", false), ("ctrl", false)];
+    assert_eq!(stream, expected.map(|(key, pressed)| (key.to_string(), pressed)));
+
+    Ok(())
+}
+
+#[test]
+fn test_config_validation_rejects_bad_key_expressions() {
+    for key in ["ctrl++", "shfit+a", "ctrl+", "'unterminated"] {
+        let config = mock_config(&format!(
+            r#"{{"process_name": "app", "independent_keys": [{{"key": "{}", "interval": "1s"}}]}}"#,
+            key
+        ));
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.starts_with("independent_keys[0]:"), "{}", error);
+        assert!(error.contains("column"), "{}", error);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_xsendevent_types_shifted_characters() -> Result<()> {
+    use process_key_sender::backend::{BackendOptions, InputBackend, XSendEventBackend};
+    use x11rb::protocol::xproto::KeyButMask;
+
+    if std::env::var_os("DISPLAY").is_none() {
+        eprintln!("skipping: no X display");
+        return Ok(());
+    }
+
+    let _guard = X11_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (conn, _window) = create_pid_window()?;
+
+    let backend = XSendEventBackend::new(&BackendOptions::default())?;
+    backend.type_char(std::process::id() as u64, 'A', DEFAULT_HOLD)?;
+
+    assert!(wait_for_key_press(&conn)?.state.contains(KeyButMask::SHIFT));
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_text_action_event_stream() -> Result<()> {
+    use process_key_sender::automation::{RunControl, run_key_sequence};
+    use process_key_sender::backend::mock::KeyEventKind::Press;
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "chat",
+        "key_sequence": [{"text": "Hi! é", "char_delay": "50ms", "interval_after": "1ms"}],
+        "loop_sequence": false
+    }
+    "#);
+    config.validate()?;
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let start = tokio::time::Instant::now();
+    run_key_sequence(&config, &key_sender, 1, &RunControl::new()).await?;
+
+    let presses: Vec<String> = mock.key_stream().into_iter()
+        .filter(|(_, kind)| *kind == Press)
+        .map(|(key, _)| key)
+        .collect();
+    assert_eq!(presses, ["shift", "h", "i", "shift", "1", "space", "é"]);
+
+    // Four gaps between five characters, then the interval
+    assert_eq!(start.elapsed(), Duration::from_millis(4 * 50 + 1));
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_press_hold_release_event_stream() -> Result<()> {
+    use process_key_sender::automation::{RunControl, run_key_sequence};
+    use process_key_sender::backend::mock::KeyEventKind::{Press, Release};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game",
+        "key_sequence": [
+            {"press": "shift", "interval_after": "10ms"},
+            {"key": "a", "interval_after": "10ms"},
+            {"hold": "ctrl+w", "duration": "2s", "interval_after": "10ms"},
+            {"release": "shift", "interval_after": "10ms"}
+        ],
+        "loop_sequence": false
+    }
+    "#);
+    config.validate()?;
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let start = tokio::time::Instant::now();
+    run_key_sequence(&config, &key_sender, 1, &RunControl::new()).await?;
+
+    let expected = [
+        ("shift", Press), ("a", Press), ("a", Release),
+        ("ctrl", Press), ("w", Press), ("w", Release), ("ctrl", Release),
+        ("shift", Release),
+    ];
+    assert_eq!(mock.key_stream(), expected.map(|(key, kind)| (key.to_string(), kind)));
+    assert_eq!(start.elapsed(), Duration::from_millis(2000 + 4 * 10));
+    assert!(key_sender.held_keys().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_shutdown_during_hold_releases_keys() -> Result<()> {
+    use process_key_sender::automation::{RunControl, Targets, run_configured};
+    use process_key_sender::backend::mock::KeyEventKind::{Press, Release};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game",
+        "key_sequence": [
+            {"press": "shift", "interval_after": "10ms"},
+            {"hold": "w", "duration": "10s", "interval_after": "10ms"}
+        ]
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let control = RunControl::new();
+
+    let remote = control.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        remote.shutdown.trigger();
+    });
+
+    run_configured(&config, &key_sender, &Targets::from(1), &control).await?;
+
+    let expected = [("shift", Press), ("w", Press), ("w", Release), ("shift", Release)];
+    assert_eq!(mock.key_stream(), expected.map(|(key, kind)| (key.to_string(), kind)));
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_mouse_action_event_stream() -> Result<()> {
+    use process_key_sender::automation::{RunControl, Targets, run_configured};
+    use process_key_sender::backend::mock::KeyEventKind::{Move, Press, Release, Scroll};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game",
+        "key_sequence": [
+            {"move_to": [100, 200], "interval_after": "10ms"},
+            {"move_by": [-5, 5], "interval_after": "10ms"},
+            {"click": "left", "interval_after": "10ms"},
+            {"mouse_press": "right", "interval_after": "10ms"},
+            {"scroll": [1, -2], "interval_after": "10ms"},
+            {"mouse_release": "right", "interval_after": "10ms"},
+            {"mouse_press": "middle", "interval_after": "10ms"}
+        ],
+        "loop_sequence": false
+    }
+    "#);
+    config.validate()?;
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    run_configured(&config, &key_sender, &Targets::from(1), &RunControl::new()).await?;
+
+    // The middle button is still down when the sequence ends and gets released
+    let expected = [
+        ("to 100,200", Move), ("by -5,5", Move),
+        ("mouse_left", Press), ("mouse_left", Release),
+        ("mouse_right", Press), ("1,-2", Scroll), ("mouse_right", Release),
+        ("mouse_middle", Press), ("mouse_middle", Release),
+    ];
+    assert_eq!(mock.key_stream(), expected.map(|(key, kind)| (key.to_string(), kind)));
+    assert!(key_sender.held_keys().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_layout_translates_keys_and_text() -> Result<()> {
+    use process_key_sender::automation::{RunControl, run_key_sequence};
+    use process_key_sender::backend::mock::KeyEventKind::{Press, Release};
+    use process_key_sender::layout::Layout;
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "editor",
+        "key_sequence": [
+            {"key": "ctrl+z", "interval_after": "10ms"},
+            {"text": "y@", "interval_after": "10ms"}
+        ],
+        "layout": "de",
+        "loop_sequence": false
+    }
+    "#);
+    config.validate()?;
+    assert_eq!(config.layout, Some(Layout::De));
+
+    // The physical keys a German keyboard needs, named by their US position
+    let mock = Arc::new(MockBackend::new().with_layout(Layout::De));
+    let key_sender = KeySender::with_backend(mock.clone());
+    run_key_sequence(&config, &key_sender, 1, &RunControl::new()).await?;
+
+    let expected = [
+        ("ctrl", Press), ("y", Press), ("y", Release), ("ctrl", Release),
+        ("z", Press), ("z", Release),
+        ("ralt", Press), ("q", Press), ("q", Release), ("ralt", Release),
+    ];
+    assert_eq!(mock.key_stream(), expected.map(|(key, kind)| (key.to_string(), kind)));
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_seeded_random_intervals_repeat() -> Result<()> {
+    use process_key_sender::automation::{RunControl, run_key_sequence};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+    use tokio::time::Instant;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game.exe",
+        "key_sequence": [
+            {"key": "a", "interval_after": "900ms..1200ms"},
+            {"key": "b", "interval_after": "1s±10%"}
+        ],
+        "repeat_count": 10,
+        "seed": 42,
+        "distribution": "normal",
+        "hold_time": "1ms..2ms"
+    }
+    "#);
+    config.validate()?;
+
+    // With the clock paused, elapsed time is exactly the sum of the drawn intervals
+    let run = || async {
+        let mock = Arc::new(MockBackend::new());
+        let key_sender = KeySender::with_backend(mock.clone());
+        let start = Instant::now();
+        run_key_sequence(&config, &key_sender, 1, &RunControl::new()).await?;
+        anyhow::Ok((start.elapsed(), mock.key_stream().len()))
+    };
+
+    let (first, events) = run().await?;
+    let (second, _) = run().await?;
+    assert_eq!(first, second);
+    assert_eq!(events, 2 * 2 * 10);
+    assert!(first > Duration::from_millis(1800 * 10) && first < Duration::from_millis(2300 * 10));
+    assert_ne!(first, Duration::from_millis(2050 * 10));
+
+    Ok(())
+}
+
+#[test]
+fn test_check_with_reports_backend_limits() -> Result<()> {
+    use process_key_sender::{InputBackend, KeySender};
+    use std::sync::Arc;
+
+    /// A keyboard without an f5 key and without a mouse
+    struct NoF5;
+
+    impl InputBackend for NoF5 {
+        fn name(&self) -> &'static str {
+            "nof5"
+        }
+
+        fn validate_key(&self, key: &str) -> Result<()> {
+            if key == "f5" {
+                anyhow::bail!("Unsupported key: {}", key);
+            }
+            Ok(())
+        }
+
+        fn press(&self, _target: u64, _key: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn release(&self, _target: u64, _key: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    let config = mock_config(r#"
+    {
+        "process_name": "app",
+        "key_sequence": [
+            {"key": "ctrl+f5", "interval_after": "1s"},
+            {"key": "shfit", "interval_after": "1s"},
+            {"click": "left", "interval_after": "20ms"},
+            {"text": "ok", "interval_after": "1s"}
+        ]
+    }
+    "#);
+
+    let key_sender = KeySender::with_backend(Arc::new(NoF5));
+    let report = config.check_with(&key_sender);
+    let errors: Vec<String> = report.errors.iter().map(ToString::to_string).collect();
+
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+    // A key that doesn't parse is reported once, not again by the backend
+    assert!(errors[0].starts_with("key_sequence[1]: unknown key 'shfit'"), "{}", errors[0]);
+    // Backend problems come after those of the config itself
+    assert_eq!(errors[1], "key_sequence[0]: ctrl+f5: Unsupported key: f5");
+    assert_eq!(errors[2], "key_sequence[2]: click left: The nof5 backend can't send mouse input");
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].path, "key_sequence[2]");
+
+    // Without a backend only the config itself is checked
+    assert_eq!(config.check().errors.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_process_matcher_finds_running_process() -> Result<()> {
+    use process_key_sender::{ProcessFinder, ProcessMatcher, TargetSpec};
+
+    let exe = std::env::current_exe()?;
+    let name = exe.file_name().unwrap().to_string_lossy().to_string();
+    let mut finder = ProcessFinder::new();
+
+    let by_exe = ProcessMatcher::new(&TargetSpec {
+        exe: Some(exe.to_string_lossy().to_string()),
+        ..Default::default()
+    })?;
+    let found = finder.find_matches(&by_exe)?;
+    assert!(found.iter().any(|process| process.pid == std::process::id()), "{:?}", found);
+
+    // The test binary's name is long enough to be truncated on Linux
+    assert!(finder.find_process_window(&name)?.is_some());
+    // Only whole names match
+    assert_eq!(finder.find_process_window(&name[..name.len() - 1])?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lockstep_broadcast_sends_each_step_to_every_target() -> Result<()> {
+    use process_key_sender::automation::{RunControl, Targets, run_configured};
+    use process_key_sender::backend::mock::KeyEventKind::{Press, Release};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let mut config = mock_config(r#"
+    {
+        "target": {"name": "game"},
+        "target_mode": "all",
+        "broadcast": "lockstep",
+        "loop_sequence": false,
+        "key_sequence": [
+            {"key": "a", "interval_after": "1ms"},
+            {"hold": "b", "duration": "1ms", "interval_after": "1ms"}
+        ]
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    run_configured(&config, &key_sender, &Targets::new(vec![2, 1]), &RunControl::new()).await?;
+
+    let stream: Vec<(u64, String, _)> = mock.events().into_iter().map(|e| (e.target, e.key, e.kind)).collect();
+    assert_eq!(stream, [
+        (1, "a".to_string(), Press),
+        (1, "a".to_string(), Release),
+        (2, "a".to_string(), Press),
+        (2, "a".to_string(), Release),
+        (1, "b".to_string(), Press),
+        (2, "b".to_string(), Press),
+        (1, "b".to_string(), Release),
+        (2, "b".to_string(), Release),
+    ]);
+
+    // Independently scheduled runs also finish once every target is done
+    config.broadcast = Default::default();
+    mock.clear();
+    run_configured(&config, &key_sender, &Targets::new(vec![1, 2]), &RunControl::new()).await?;
+
+    for target in [1, 2] {
+        let keys: Vec<String> = mock.events().into_iter().filter(|e| e.target == target).map(|e| e.key).collect();
+        assert_eq!(keys, ["a", "a", "b", "b"], "target {}", target);
+    }
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_independent_broadcast_follows_target_changes() -> Result<()> {
+    use process_key_sender::automation::{RunControl, Targets, run_configured};
+    use process_key_sender::backend::mock::KeyEventKind::Press;
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game",
+        "target_mode": "all",
+        "independent_keys": [{"key": "a", "interval": "1s"}]
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let targets = Targets::new(vec![1, 2]);
+    let control = RunControl::new();
+
+    let run = {
+        let (config, key_sender, targets, control) = (config.clone(), key_sender.clone(), targets.clone(), control.clone());
+        tokio::spawn(async move { run_configured(&config, &key_sender, &targets, &control).await })
+    };
+
+    let presses_on = |target: u64| mock.events().iter().filter(|e| e.target == target && e.kind == Press).count();
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!((presses_on(1), presses_on(2), presses_on(3)), (2, 2, 0));
+
+    // Process 1 exits and process 3 starts
+    assert!(targets.set(vec![3, 2]));
+    tokio::time::sleep(Duration::from_millis(2000)).await;
+    assert_eq!((presses_on(1), presses_on(2), presses_on(3)), (2, 4, 2));
+
+    control.shutdown.trigger();
+    run.await??;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_target_check_skips_and_replaces_windows() -> Result<()> {
+    use process_key_sender::automation::{RunControl, Targets, run_configured};
+    use process_key_sender::backend::mock::KeyEventKind::Press;
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    let config = mock_config(r#"
+    {
+        "pid": 1,
+        "loop_sequence": false,
+        "key_sequence": [
+            {"key": "a", "interval_after": "1ms"},
+            {"key": "b", "interval_after": "1ms"},
+            {"key": "c", "interval_after": "1ms"}
+        ]
+    }
+    "#);
+
+    // Window 1 is gone at the second step and comes back as window 2
+    let checks = Arc::new(AtomicU32::new(0));
+    let check = {
+        let checks = checks.clone();
+        Arc::new(move |window_id: u64| match checks.fetch_add(1, Ordering::Relaxed) {
+            0 => Some(window_id),
+            1 => None,
+            _ => Some(2),
+        })
+    };
+    let targets = Targets::from(1).with_check(check);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    run_configured(&config, &key_sender, &targets, &RunControl::new()).await?;
+
+    let sent: Vec<(u64, String)> = mock.events().into_iter().filter(|e| e.kind == Press).map(|e| (e.target, e.key)).collect();
+    assert_eq!(sent, [(1, "a".to_string()), (2, "c".to_string())]);
+    assert_eq!(targets.get(), [2]);
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_lost_target_holds_the_loops() -> Result<()> {
+    use process_key_sender::automation::{RunControl, run_independent_keys};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::Arc;
+
+    let config = mock_config(r#"
+    {
+        "process_name": "game",
+        "independent_keys": [{"key": "a", "interval": "1s"}]
+    }
+    "#);
+
+    let mock = Arc::new(MockBackend::new());
+    let key_sender = KeySender::with_backend(mock.clone());
+    let control = RunControl::new();
+
+    let run = {
+        let (config, key_sender, control) = (config.clone(), key_sender.clone(), control.clone());
+        tokio::spawn(async move { run_independent_keys(&config, &key_sender, 1, &control).await })
+    };
+    let presses = || mock.key_stream().len() / 2;
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(presses(), 2);
+
+    control.target_lost.set_paused(true);
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(presses(), 2);
+    assert!(control.status().target_lost);
+
+    // A user's resume doesn't send while the target is still gone
+    control.pause.set_paused(true);
+    control.pause.set_paused(false);
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(presses(), 2);
+
+    control.target_lost.set_paused(false);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(presses(), 3);
+
+    // Shutdown still ends a run that is waiting for its target
+    control.target_lost.set_paused(true);
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    control.shutdown.trigger();
+    run.await??;
+
+    Ok(())
+}
+
+/// Start a run from `json` with `overrides` applied the way main does, reload it
+/// once and return the config the reload produced
+async fn reload_with_overrides(json: &str, overrides: CliOverrides) -> Result<Config> {
+    use process_key_sender::automation::{RunControl, Targets, run_with_reload};
+    use process_key_sender::{KeySender, MockBackend};
+    use std::sync::{Arc, Mutex};
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config.json");
+    std::fs::write(&path, json)?;
+
+    let loaded = Mutex::new(Vec::new());
+    let load_config = || {
+        let mut config = Config::from_file(path.to_str().unwrap())?;
+        overrides.apply(&mut config);
+        config.validate()?;
+        loaded.lock().unwrap().push(config.clone());
+        Ok(config)
+    };
+
+    let key_sender = KeySender::with_backend(Arc::new(MockBackend::new()));
+    let control = RunControl::new();
+    let reload = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let result = control.request_reload().await;
+        control.shutdown.trigger();
+        result
+    };
+
+    let targets = Targets::from(1);
+    let (run, reloaded) = tokio::join!(
+        run_with_reload(load_config()?, &key_sender, &targets, &control, load_config),
+        reload
+    );
+    run?;
+    reloaded.map_err(anyhow::Error::msg)?;
+
+    let loaded = loaded.into_inner().unwrap();
+    assert_eq!(loaded.len(), 2);
+    Ok(loaded.into_iter().next_back().unwrap())
+}
+
+#[tokio::test]
+async fn test_reload_keeps_pid_override() -> Result<()> {
+    // The file names no target of its own, so it only validates with --pid applied
+    let overrides = CliOverrides {
+        pid: Some(4242),
+        backend: Some(BackendKind::Mock),
+        ..CliOverrides::default()
+    };
+    let config = reload_with_overrides(
+        r#"{"independent_keys": [{"key": "a", "interval": "10ms"}]}"#,
+        overrides,
+    )
+    .await?;
+
+    assert_eq!(config.pid, Some(4242));
+    assert!(config.process_name.is_empty());
+    assert_eq!(config.backend, BackendKind::Mock);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reload_keeps_all_override() -> Result<()> {
+    use process_key_sender::process_finder::TargetMode;
+
+    let overrides = CliOverrides {
+        all: true,
+        backend: Some(BackendKind::Mock),
+        ..CliOverrides::default()
+    };
+    let config = reload_with_overrides(
+        r#"{"process_name": "game.exe", "target_mode": "single", "independent_keys": [{"key": "a", "interval": "10ms"}]}"#,
+        overrides,
+    )
+    .await?;
+
+    assert_eq!(config.target_mode, TargetMode::All);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reload_keeps_seed_override() -> Result<()> {
+    let overrides = CliOverrides {
+        seed: Some(7),
+        backend: Some(BackendKind::Mock),
+        ..CliOverrides::default()
+    };
+    let config = reload_with_overrides(
+        r#"{"process_name": "game.exe", "seed": 1, "independent_keys": [{"key": "a", "interval": "10ms"}]}"#,
+        overrides,
+    )
+    .await?;
+
+    assert_eq!(config.seed, Some(7));
+
+    Ok(())
+}